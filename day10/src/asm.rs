@@ -0,0 +1,129 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::cpu::{Instruction, Opcode, Operand, Register, INSTRUCTION_SET};
+
+#[derive(Debug, PartialEq)]
+pub enum AsmErrorKind {
+    UnknownMnemonic(String),
+    MissingOperand(&'static str),
+    InvalidOperand(String),
+    UnexpectedOperand(String),
+    UnknownLabel(String),
+    DuplicateLabel(String),
+}
+
+/// Why a program failed to assemble, and the source line (from 1) at fault.
+#[derive(Debug, PartialEq)]
+pub struct AsmError {
+    pub line: usize,
+    pub kind: AsmErrorKind,
+}
+
+impl Display for AsmErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownMnemonic(m) => write!(f, "unknown instruction `{}`", m),
+            Self::MissingOperand(m) => write!(f, "`{}` is missing an operand", m),
+            Self::InvalidOperand(o) => write!(f, "invalid operand `{}`", o),
+            Self::UnexpectedOperand(o) => write!(f, "unexpected operand `{}`", o),
+            Self::UnknownLabel(l) => write!(f, "unknown label `{}`", l),
+            Self::DuplicateLabel(l) => write!(f, "label `{}` is defined twice", l),
+        }
+    }
+}
+
+impl Display for AsmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+fn operand(s: &str) -> Result<Operand, AsmErrorKind> {
+    Register::parse(s)
+        .map(Operand::Register)
+        .or_else(|| s.parse().ok().map(Operand::Value))
+        .ok_or_else(|| AsmErrorKind::InvalidOperand(s.to_string()))
+}
+
+/// Jump targets are labels or instruction indices.
+fn target(s: &str, labels: &HashMap<&str, usize>) -> Result<usize, AsmErrorKind> {
+    match labels.get(s) {
+        Some(&target) => Ok(target),
+        None if s.starts_with(|c: char| c.is_ascii_digit()) => s
+            .parse()
+            .map_err(|_| AsmErrorKind::InvalidOperand(s.to_string())),
+        None => Err(AsmErrorKind::UnknownLabel(s.to_string())),
+    }
+}
+
+fn parse_instruction(
+    line: &str,
+    labels: &HashMap<&str, usize>,
+) -> Result<Instruction, AsmErrorKind> {
+    let mut parts = line.split_whitespace();
+    let word = parts.next().unwrap();
+    let (info, register) = INSTRUCTION_SET
+        .iter()
+        .find_map(|info| match word.strip_prefix(info.mnemonic)? {
+            "" if !info.register_suffix => Some((info, None)),
+            r if info.register_suffix => Some((info, Some(Register::parse(r)?))),
+            _ => None,
+        })
+        .ok_or_else(|| AsmErrorKind::UnknownMnemonic(word.to_string()))?;
+    let operands = parts.collect::<Vec<_>>();
+
+    let instruction = match (info.opcode, register, &operands[..]) {
+        (Opcode::Noop, _, []) => Instruction::Noop,
+        (Opcode::Add, Some(r), [o, ..]) => Instruction::Add(r, operand(o)?),
+        (Opcode::Mul, Some(r), [o, ..]) => Instruction::Mul(r, operand(o)?),
+        (Opcode::Set, Some(r), [o, ..]) => Instruction::Set(r, operand(o)?),
+        (Opcode::Jmp, _, [t]) => Instruction::Jmp(None, target(t, labels)?),
+        (Opcode::Jmp, _, [r, t, ..]) => Instruction::Jmp(
+            Some(Register::parse(r).ok_or_else(|| AsmErrorKind::InvalidOperand(r.to_string()))?),
+            target(t, labels)?,
+        ),
+        (Opcode::Noop, _, [o, ..]) => return Err(AsmErrorKind::UnexpectedOperand(o.to_string())),
+        _ => return Err(AsmErrorKind::MissingOperand(info.mnemonic)),
+    };
+
+    let expected = match instruction {
+        Instruction::Noop => 0,
+        Instruction::Jmp(Some(_), _) => 2,
+        _ => 1,
+    };
+    match operands.get(expected) {
+        Some(extra) => Err(AsmErrorKind::UnexpectedOperand(extra.to_string())),
+        None => Ok(instruction),
+    }
+}
+
+/// Assembles a program, one instruction per line. Lines may start with a
+/// `label:` to jump to and anything after a `;` is a comment.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, AsmError> {
+    let mut labels = HashMap::new();
+    let mut lines = vec![];
+    for (i, line) in source.lines().enumerate() {
+        let mut line = line.split(';').next().unwrap().trim();
+        if let Some((label, rest)) = line.split_once(':') {
+            if labels.insert(label.trim(), lines.len()).is_some() {
+                return Err(AsmError {
+                    line: i + 1,
+                    kind: AsmErrorKind::DuplicateLabel(label.trim().to_string()),
+                });
+            }
+            line = rest.trim();
+        }
+        if !line.is_empty() {
+            lines.push((i + 1, line));
+        }
+    }
+
+    lines
+        .into_iter()
+        .map(|(line, text)| {
+            parse_instruction(text, &labels).map_err(|kind| AsmError { line, kind })
+        })
+        .collect()
+}
@@ -0,0 +1,204 @@
+use std::fmt::Display;
+
+/// Names of the registers, `x` being the one driving the sprite.
+pub const REGISTER_NAMES: &[u8] = b"xyzw";
+pub const REGISTERS: usize = REGISTER_NAMES.len();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Register(pub usize);
+
+pub const X: Register = Register(0);
+
+impl Register {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.as_bytes() {
+            [c] => REGISTER_NAMES.iter().position(|r| r == c).map(Self),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", REGISTER_NAMES[self.0] as char)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Value(i32),
+    Register(Register),
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Value(v) => write!(f, "{}", v),
+            Self::Register(r) => write!(f, "{}", r),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    Noop,
+    Add,
+    Mul,
+    Set,
+    Jmp,
+}
+
+pub struct OpcodeInfo {
+    pub opcode: Opcode,
+    pub mnemonic: &'static str,
+    /// Whether the mnemonic is followed by the target register, as in `addx`.
+    pub register_suffix: bool,
+    pub cycles: u64,
+}
+
+pub const INSTRUCTION_SET: [OpcodeInfo; 5] = [
+    OpcodeInfo {
+        opcode: Opcode::Noop,
+        mnemonic: "noop",
+        register_suffix: false,
+        cycles: 1,
+    },
+    OpcodeInfo {
+        opcode: Opcode::Add,
+        mnemonic: "add",
+        register_suffix: true,
+        cycles: 2,
+    },
+    OpcodeInfo {
+        opcode: Opcode::Mul,
+        mnemonic: "mul",
+        register_suffix: true,
+        cycles: 2,
+    },
+    OpcodeInfo {
+        opcode: Opcode::Set,
+        mnemonic: "set",
+        register_suffix: true,
+        cycles: 1,
+    },
+    OpcodeInfo {
+        opcode: Opcode::Jmp,
+        mnemonic: "jmp",
+        register_suffix: false,
+        cycles: 1,
+    },
+];
+
+impl Opcode {
+    pub fn info(self) -> &'static OpcodeInfo {
+        INSTRUCTION_SET.iter().find(|i| i.opcode == self).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Noop,
+    Add(Register, Operand),
+    Mul(Register, Operand),
+    Set(Register, Operand),
+    /// Jumps to an instruction index, if given only when the register isn't 0.
+    Jmp(Option<Register>, usize),
+}
+
+impl Instruction {
+    pub fn opcode(&self) -> Opcode {
+        match self {
+            Self::Noop => Opcode::Noop,
+            Self::Add(..) => Opcode::Add,
+            Self::Mul(..) => Opcode::Mul,
+            Self::Set(..) => Opcode::Set,
+            Self::Jmp(..) => Opcode::Jmp,
+        }
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.opcode().info().cycles
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mnemonic = self.opcode().info().mnemonic;
+        match self {
+            Self::Noop => write!(f, "{}", mnemonic),
+            Self::Add(r, o) | Self::Mul(r, o) | Self::Set(r, o) => {
+                write!(f, "{}{} {}", mnemonic, r, o)
+            }
+            Self::Jmp(Some(r), target) => write!(f, "{} {} {}", mnemonic, r, target),
+            Self::Jmp(None, target) => write!(f, "{} {}", mnemonic, target),
+        }
+    }
+}
+
+pub type Registers = [i32; REGISTERS];
+
+/// The handheld's CPU, `x` starts at 1 and the other registers at 0.
+pub struct Cpu<'a> {
+    program: &'a [Instruction],
+    pub pc: usize,
+    pub registers: Registers,
+    /// Number of cycles completed so far.
+    pub cycle: u64,
+}
+
+impl<'a> Cpu<'a> {
+    pub fn new(program: &'a [Instruction]) -> Self {
+        let mut registers = [0; REGISTERS];
+        registers[X.0] = 1;
+        Self {
+            program,
+            pc: 0,
+            registers,
+            cycle: 0,
+        }
+    }
+
+    pub fn next_instruction(&self) -> Option<Instruction> {
+        self.program.get(self.pc).copied()
+    }
+
+    /// Runs the next instruction, calling `tick` with the number of every
+    /// cycle it takes, counting from 1, and the registers during it.
+    /// Arithmetic wraps around like on a 32-bit machine.
+    /// Returns `false` once the program has ended.
+    pub fn step(&mut self, mut tick: impl FnMut(u64, &Registers)) -> bool {
+        let Some(instruction) = self.next_instruction() else {
+            return false;
+        };
+        for _ in 0..instruction.cycles() {
+            self.cycle += 1;
+            tick(self.cycle, &self.registers);
+        }
+
+        let value = |registers: &Registers, operand| match operand {
+            Operand::Value(v) => v,
+            Operand::Register(r) => registers[r.0],
+        };
+        self.pc += 1;
+        match instruction {
+            Instruction::Noop => {}
+            Instruction::Add(r, o) => {
+                self.registers[r.0] = self.registers[r.0].wrapping_add(value(&self.registers, o))
+            }
+            Instruction::Mul(r, o) => {
+                self.registers[r.0] = self.registers[r.0].wrapping_mul(value(&self.registers, o))
+            }
+            Instruction::Set(r, o) => self.registers[r.0] = value(&self.registers, o),
+            Instruction::Jmp(condition, target) => {
+                if condition.is_none_or(|r| self.registers[r.0] != 0) {
+                    self.pc = target;
+                }
+            }
+        }
+        true
+    }
+
+    pub fn run(&mut self, mut tick: impl FnMut(u64, &Registers)) {
+        while self.step(&mut tick) {}
+    }
+}
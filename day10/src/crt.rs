@@ -0,0 +1,65 @@
+/// The handheld's screen, drawn one pixel per cycle row by row. Programs
+/// running past the last pixel start over at the top left.
+pub struct Crt {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+}
+
+impl Crt {
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(width > 0 && height > 0, "The screen can't be empty");
+        Self {
+            width,
+            height,
+            pixels: vec![false; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Draws the pixel of a cycle, counting from 1. It is lit if the three
+    /// pixels wide sprite centred on `x` covers its column.
+    pub fn draw(&mut self, cycle: u64, x: i32) {
+        let index = (cycle - 1) as usize % self.pixels.len();
+        let column = (index % self.width) as i64;
+        self.pixels[index] = (x as i64 - 1..=x as i64 + 1).contains(&column);
+    }
+
+    pub fn pixel(&self, column: usize, row: usize) -> bool {
+        self.pixels[row * self.width + column]
+    }
+
+    pub fn render(&self, lit: char, dark: char) -> String {
+        let mut out = String::with_capacity((self.width + 1) * self.height);
+        for row in 0..self.height {
+            out.extend((0..self.width).map(|column| match self.pixel(column, row) {
+                true => lit,
+                false => dark,
+            }));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Binary PBM (P4) image of the screen, lit pixels are black.
+    pub fn to_pbm(&self) -> Vec<u8> {
+        let mut out = format!("P4\n{} {}\n", self.width, self.height).into_bytes();
+        for row in self.pixels.chunks(self.width) {
+            for byte in row.chunks(8) {
+                let bits = byte
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |b, (i, &p)| b | (p as u8) << (7 - i));
+                out.push(bits);
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,306 @@
+use std::{
+    collections::VecDeque,
+    fmt::{Display, Write as _},
+    io::{self, BufRead, Write},
+};
+
+use crate::cpu::{Cpu, Instruction, Register, REGISTERS, REGISTER_NAMES, X};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Stops before the instruction running during this cycle.
+    Cycle(u64),
+    /// Stops before the instruction at this index.
+    Instruction(usize),
+    /// Stops before any instruction while the register holds this value.
+    Register(Register, i32),
+}
+
+/// Written the way the `break` command takes it.
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cycle(cycle) => write!(f, "cycle {}", cycle),
+            Self::Instruction(pc) => write!(f, "pc {}", pc),
+            Self::Register(r, value) => write!(f, "{} {}", r, value),
+        }
+    }
+}
+
+/// Cycles kept in the trace unless changed with `trace-limit`.
+pub const TRACE_LIMIT: usize = 100_000;
+
+/// Why the program stopped running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Step,
+    Breakpoint(Breakpoint),
+    /// The watched `x` changed at the end of `cycle`.
+    Watch {
+        cycle: u64,
+        old: i32,
+        new: i32,
+    },
+    Halted,
+}
+
+impl Display for Stop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Step => write!(f, "Stepped"),
+            Self::Breakpoint(b) => write!(f, "Stopped at breakpoint `{}`", b),
+            Self::Watch { cycle, old, new } => {
+                write!(f, "x changed from {} to {} after cycle {}", old, new, cycle)
+            }
+            Self::Halted => write!(f, "Program halted"),
+        }
+    }
+}
+
+/// A line typed at the debugger prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// `break cycle N`, `break pc N` or `break REGISTER VALUE`.
+    Break(Breakpoint),
+    /// `watch` toggles stopping whenever `x` changes.
+    Watch,
+    Registers,
+    /// `trace-limit N`
+    TraceLimit(usize),
+    /// `trace FILE` writes the trace as CSV.
+    Trace(String),
+    Step,
+    Continue,
+    Quit,
+}
+
+impl Command {
+    /// Parses a prompt line, blank ones are `None`.
+    pub fn parse(line: &str) -> Result<Option<Self>, String> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let number = |s: &str| s.parse().map_err(|_| format!("invalid number `{}`", s));
+        Ok(Some(match words[..] {
+            [] => return Ok(None),
+            ["break", "cycle", n] => Self::Break(Breakpoint::Cycle(number(n)?)),
+            ["break", "pc", n] => Self::Break(Breakpoint::Instruction(number(n)? as usize)),
+            ["break", r, v] => {
+                let r = Register::parse(r).ok_or_else(|| format!("unknown register `{}`", r))?;
+                let v = v.parse().map_err(|_| format!("invalid value `{}`", v))?;
+                Self::Break(Breakpoint::Register(r, v))
+            }
+            ["break", ..] => {
+                return Err("usage: break cycle N | break pc N | break REGISTER VALUE".into())
+            }
+            ["watch"] => Self::Watch,
+            ["regs"] => Self::Registers,
+            ["trace-limit", n] => match n.parse() {
+                Ok(limit) if limit > 0 => Self::TraceLimit(limit),
+                _ => return Err("the trace limit must be a positive number".into()),
+            },
+            ["trace-limit", ..] => return Err("usage: trace-limit N".into()),
+            ["trace", path] => Self::Trace(path.to_string()),
+            ["trace", ..] => return Err("usage: trace FILE".into()),
+            ["step" | "s"] => Self::Step,
+            ["continue" | "c"] => Self::Continue,
+            ["quit"] => Self::Quit,
+            [command, ..] => return Err(format!("unknown command `{}`", command)),
+        }))
+    }
+}
+
+/// One cycle of execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub cycle: u64,
+    pub pc: usize,
+    pub instruction: Instruction,
+    pub x_during: i32,
+    pub x_after: i32,
+}
+
+/// Runs a program instruction by instruction, keeping a trace of the last
+/// `trace_limit` cycles.
+pub struct Debugger<'a> {
+    cpu: Cpu<'a>,
+    breakpoints: Vec<Breakpoint>,
+    /// Whether execution stopped at a breakpoint and hasn't moved since.
+    at_breakpoint: bool,
+    watch_x: bool,
+    trace: VecDeque<TraceEntry>,
+    trace_limit: usize,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(program: &'a [Instruction]) -> Self {
+        Self {
+            cpu: Cpu::new(program),
+            breakpoints: vec![],
+            at_breakpoint: false,
+            watch_x: false,
+            trace: VecDeque::new(),
+            trace_limit: TRACE_LIMIT,
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(breakpoint);
+        self.breakpoints.len() - 1
+    }
+
+    pub fn watch_x(&mut self, watch: bool) {
+        self.watch_x = watch;
+    }
+
+    /// Keeps only the last `limit` cycles in the trace from now on.
+    pub fn set_trace_limit(&mut self, limit: usize) {
+        assert!(limit > 0, "The trace must keep at least one cycle");
+        self.trace_limit = limit;
+        let excess = self.trace.len().saturating_sub(limit);
+        self.trace.drain(..excess);
+    }
+
+    fn hit(&self, breakpoint: &Breakpoint, instruction: &Instruction) -> bool {
+        match *breakpoint {
+            Breakpoint::Cycle(cycle) => {
+                (self.cpu.cycle + 1..=self.cpu.cycle + instruction.cycles()).contains(&cycle)
+            }
+            Breakpoint::Instruction(pc) => self.cpu.pc == pc,
+            Breakpoint::Register(r, value) => self.cpu.registers[r.0] == value,
+        }
+    }
+
+    /// Runs a single instruction.
+    pub fn step(&mut self) -> Stop {
+        let Some(instruction) = self.cpu.next_instruction() else {
+            return Stop::Halted;
+        };
+        self.at_breakpoint = false;
+        let pc = self.cpu.pc;
+        let (trace, limit) = (&mut self.trace, self.trace_limit);
+        self.cpu.step(|cycle, registers| {
+            if trace.len() == limit {
+                trace.pop_front();
+            }
+            trace.push_back(TraceEntry {
+                cycle,
+                pc,
+                instruction,
+                x_during: registers[X.0],
+                x_after: registers[X.0],
+            })
+        });
+
+        let x = self.cpu.registers[X.0];
+        let last = self.trace.back_mut().unwrap();
+        let old = last.x_during;
+        last.x_after = x;
+        if self.watch_x && old != x {
+            return Stop::Watch {
+                cycle: self.cpu.cycle,
+                old,
+                new: x,
+            };
+        }
+        Stop::Step
+    }
+
+    /// Runs until a breakpoint, a change of the watched `x` or the end. When
+    /// stopped at a breakpoint, the first instruction runs without checking
+    /// them, so that continuing moves on.
+    pub fn resume(&mut self) -> Stop {
+        loop {
+            let Some(instruction) = self.cpu.next_instruction() else {
+                return Stop::Halted;
+            };
+            if !self.at_breakpoint {
+                if let Some(&b) = self.breakpoints.iter().find(|b| self.hit(b, &instruction)) {
+                    self.at_breakpoint = true;
+                    return Stop::Breakpoint(b);
+                }
+            }
+            if let stop @ Stop::Watch { .. } = self.step() {
+                return stop;
+            }
+        }
+    }
+
+    pub fn trace_csv(&self) -> String {
+        let mut out = "cycle,pc,instruction,x_during,x_after\n".to_string();
+        for t in &self.trace {
+            writeln!(
+                out,
+                "{},{},{},{},{}",
+                t.cycle, t.pc, t.instruction, t.x_during, t.x_after
+            )
+            .unwrap();
+        }
+        out
+    }
+
+    /// Cycle, registers and the next instruction, on one line.
+    pub fn state(&self) -> String {
+        let registers = (0..REGISTERS)
+            .map(|r| format!("{}={}", REGISTER_NAMES[r] as char, self.cpu.registers[r]))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let next = match self.cpu.next_instruction() {
+            Some(instruction) => instruction.to_string(),
+            None => "halted".to_string(),
+        };
+        format!(
+            "cycle {} pc {} [{}] next: {}\n",
+            self.cpu.cycle, self.cpu.pc, registers, next
+        )
+    }
+
+    /// Runs a command and writes what it has to say about it.
+    pub fn apply(&mut self, command: Command, output: &mut impl Write) -> io::Result<()> {
+        let stop = match command {
+            Command::Break(breakpoint) => {
+                let id = self.add_breakpoint(breakpoint);
+                return writeln!(output, "Breakpoint {}: {}", id, breakpoint);
+            }
+            Command::Watch => {
+                self.watch_x(!self.watch_x);
+                let state = if self.watch_x { "on" } else { "off" };
+                return writeln!(output, "Watching x {}", state);
+            }
+            Command::Registers => return write!(output, "{}", self.state()),
+            Command::TraceLimit(limit) => {
+                self.set_trace_limit(limit);
+                return writeln!(output, "Keeping the last {} cycles", limit);
+            }
+            Command::Trace(path) => {
+                return match std::fs::write(&path, self.trace_csv()) {
+                    Ok(()) => writeln!(output, "Wrote {} cycles to {}", self.trace.len(), path),
+                    Err(e) => writeln!(output, "error: {}: {}", path, e),
+                }
+            }
+            Command::Step => self.step(),
+            Command::Continue => self.resume(),
+            Command::Quit => return Ok(()),
+        };
+
+        if stop != Stop::Step {
+            writeln!(output, "{}", stop)?;
+        }
+        write!(output, "{}", self.state())
+    }
+
+    /// Prompts for commands on `output` until `quit` or the end of `input`.
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        write!(output, "(dbg) ")?;
+        output.flush()?;
+        for line in input.lines() {
+            match Command::parse(&line?) {
+                Ok(Some(Command::Quit)) => return Ok(()),
+                Ok(Some(command)) => self.apply(command, &mut output)?,
+                Ok(None) => {}
+                Err(e) => writeln!(output, "error: {}", e)?,
+            }
+            write!(output, "(dbg) ")?;
+            output.flush()?;
+        }
+        writeln!(output)
+    }
+}
@@ -1,70 +1,311 @@
-enum Instruction {
-    NOOP,
-    ADD(i32)
+mod asm;
+mod cpu;
+mod crt;
+mod debugger;
+mod ocr;
+mod signal;
+
+use std::{env, fs, io, process};
+
+use asm::assemble;
+use cpu::{Cpu, Instruction, X};
+use crt::Crt;
+use debugger::Debugger;
+use ocr::recognise;
+use signal::{parse_schedule, Periodic, Sampler, Schedule};
+
+const SCREEN_WIDTH: usize = 40;
+const SCREEN_HEIGHT: usize = 6;
+const PUZZLE_SCHEDULE: Periodic = Periodic {
+    start: 20,
+    period: 40,
+};
+
+fn simulate_cpu(instructions: &[Instruction], crt: &mut Crt, sampler: &mut Sampler<impl Schedule>) {
+    Cpu::new(instructions).run(|cycle, registers| {
+        let x = registers[X.0];
+        crt.draw(cycle, x);
+        sampler.tick(cycle, x);
+    });
 }
 
-impl From<&str> for Instruction {
-    fn from(s: &str) -> Self {
-        if s == "noop" {
-            Self::NOOP
-        } else {
-            let parts = s.split(" ").collect::<Vec<_>>();
-            assert_eq!(parts.len(), 2);
-            assert_eq!(parts[0], "addx");
-            Self::ADD(parts[1].parse().expect("Invalid number format"))
+fn main() {
+    let mut program = None;
+    let mut debug = false;
+    let mut schedule = None;
+    let mut pbm = None;
+    let mut glyphs = ('#', '.');
+    let (mut width, mut height) = (SCREEN_WIDTH, SCREEN_HEIGHT);
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--debug" => debug = true,
+            "--sample" => {
+                let spec = args.next().expect("Missing sampling schedule");
+                schedule = Some(parse_schedule(&spec).expect("Invalid sampling schedule"));
+            }
+            "--pbm" => pbm = Some(args.next().expect("Missing PBM path")),
+            "--glyphs" => {
+                let chars = args
+                    .next()
+                    .expect("Missing glyphs")
+                    .chars()
+                    .collect::<Vec<_>>();
+                match chars[..] {
+                    [lit, dark] => glyphs = (lit, dark),
+                    _ => panic!("Glyphs must be two characters, lit then dark"),
+                }
+            }
+            "--size" => {
+                let size = args.next().expect("Missing screen size");
+                let (w, h) = size.split_once('x').expect("Size must be WIDTHxHEIGHT");
+                let dimension = |n: &str| n.parse().ok().filter(|&n| n > 0);
+                width = dimension(w).expect("Screen width must be a positive number");
+                height = dimension(h).expect("Screen height must be a positive number");
+            }
+            path => program = Some(fs::read_to_string(path).expect("Failed to read program")),
         }
     }
-}
 
-fn simulate_cpu(instructions: &Vec<Instruction>) -> Vec<i32> {
-    let mut signal_values : Vec<i32> = vec![];
-    let mut cycle = 0;
+    let source = program.as_deref().unwrap_or(include_str!("input"));
+    let instructions = assemble(source).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    if debug {
+        Debugger::new(&instructions)
+            .run(io::stdin().lock(), io::stdout())
+            .expect("Failed to run debugger");
+        return;
+    }
 
-    let mut x = 1;
+    let mut crt = Crt::new(width, height);
+    let show_samples = schedule.is_some();
+    let schedule = schedule.unwrap_or_else(|| Box::new(|cycle| PUZZLE_SCHEDULE.samples(cycle)));
+    let mut sampler = Sampler::new(schedule);
+    simulate_cpu(&instructions, &mut crt, &mut sampler);
 
-    let mut inc_cycle = |x: i32| {
-        if (x-1..=x+1).contains(&(cycle % 40)) {
-            print!("#");
-        } else {
-            print!(".");
-        }
+    print!("{}", crt.render(glyphs.0, glyphs.1));
+    if let Some(path) = pbm {
+        fs::write(path, crt.to_pbm()).expect("Failed to write PBM");
+    }
 
-        cycle += 1;
-        if cycle == 20 || (cycle - 20) % 40 == 0 {
-            signal_values.push(x);
+    if show_samples {
+        for (cycle, x, strength) in sampler.samples() {
+            println!("{:>6} {:>4} {:>8}", cycle, x, strength);
         }
+    }
+    let sum = sampler.samples().iter().map(|s| s.2).sum::<i128>();
+    println!("Sum: {}", sum);
+    println!("Signal integral: {}", sampler.integral());
+    match recognise(&crt) {
+        Ok(letters) => println!("Letters: {}", letters),
+        Err(e) => println!("Couldn't read the screen, {}", e),
+    }
+}
 
-        if cycle % 40 == 0 {
-            println!();
-        }
+#[cfg(test)]
+mod tests {
+    use crate::{
+        asm::{AsmError, AsmErrorKind},
+        cpu::{Operand, Register},
+        debugger::{Breakpoint, Command, Stop},
+        ocr::OcrError,
+        *,
     };
 
-    for instruction in instructions {
-        match instruction {
-            Instruction::NOOP => inc_cycle(x),
-            Instruction::ADD(arg) => {
-                inc_cycle(x);
-                inc_cycle(x);
-                x += arg;
-            },
-        }
+    #[test]
+    fn example() {
+        let instructions = assemble(include_str!("example")).unwrap();
+        let mut crt = Crt::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+        let mut sampler = Sampler::new(PUZZLE_SCHEDULE);
+        simulate_cpu(&instructions, &mut crt, &mut sampler);
+        assert_eq!(
+            sampler.samples(),
+            [
+                (20, 21, 420),
+                (60, 19, 1140),
+                (100, 18, 1800),
+                (140, 21, 2940),
+                (180, 16, 2880),
+                (220, 18, 3960)
+            ]
+        );
+        assert_eq!(
+            crt.render('#', '.'),
+            "##..##..##..##..##..##..##..##..##..##..\n\
+             ###...###...###...###...###...###...###.\n\
+             ####....####....####....####....####....\n\
+             #####.....#####.....#####.....#####.....\n\
+             ######......######......######......####\n\
+             #######.......#######.......#######.....\n"
+        );
+        assert!(crt.pixel(0, 0) && !crt.pixel(2, 0));
+
+        let pbm = crt.to_pbm();
+        assert!(pbm.starts_with(b"P4\n40 6\n"));
+        assert_eq!(pbm.len(), 8 + 5 * 6);
+        assert_eq!(pbm[8], 0b11001100);
     }
 
-    signal_values
-}
+    #[test]
+    fn sampling() {
+        let instructions = assemble(include_str!("example")).unwrap();
+        let mut crt = Crt::new(SCREEN_WIDTH, SCREEN_HEIGHT);
 
+        let mut sampler = Sampler::new([1, 20, 500]);
+        simulate_cpu(&instructions, &mut crt, &mut sampler);
+        assert_eq!(sampler.samples(), [(1, 1, 1), (20, 21, 420)]);
 
-fn main() {
-    let instructions = include_str!("input").lines().map(Instruction::from).collect::<Vec<_>>();
-    let signal_strengths = simulate_cpu(&instructions);
+        let mut odd = Sampler::new(|cycle| cycle % 2 == 1);
+        simulate_cpu(&instructions, &mut crt, &mut odd);
+        assert_eq!(odd.samples().len(), 120);
 
-    let mut cycle = 20;
-    let mut sum = 0;
+        let mut all = Sampler::new(parse_schedule("1+1").unwrap());
+        simulate_cpu(&instructions, &mut crt, &mut all);
+        let total = all.samples().iter().map(|s| s.2).sum::<i128>();
+        assert_eq!(all.integral(), total);
+        assert_eq!(odd.integral(), total);
 
-    for signal in signal_strengths {
-        sum += cycle * signal;
-        cycle += 40;
+        let mut long = Sampler::new([]);
+        long.tick(u64::MAX, i32::MAX);
+        long.tick(u64::MAX, i32::MAX);
+        assert_eq!(long.integral(), 2 * u64::MAX as i128 * i32::MAX as i128);
+
+        assert!(parse_schedule("20+0").is_none());
     }
 
-    println!("Sum: {}", sum);
+    #[test]
+    fn letters() {
+        let instructions = assemble(include_str!("input")).unwrap();
+        let mut crt = Crt::new(SCREEN_WIDTH, SCREEN_HEIGHT);
+        simulate_cpu(&instructions, &mut crt, &mut Sampler::new([]));
+        assert_eq!(recognise(&crt), Ok("BRJLFULP".to_string()));
+
+        let instructions = assemble(include_str!("example")).unwrap();
+        simulate_cpu(&instructions, &mut crt, &mut Sampler::new([]));
+        assert_eq!(
+            recognise(&crt),
+            Err(OcrError::UnknownGlyph {
+                index: 0,
+                bitmap: "##..\n###.\n####\n####\n####\n####".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn debugger() {
+        let instructions = assemble(include_str!("example")).unwrap();
+        let mut debugger = Debugger::new(&instructions);
+        debugger.add_breakpoint(Breakpoint::Cycle(20));
+        assert_eq!(debugger.resume(), Stop::Breakpoint(Breakpoint::Cycle(20)));
+        assert_eq!(
+            debugger.state(),
+            "cycle 19 pc 10 [x=21 y=0 z=0 w=0] next: addx -1\n"
+        );
+
+        debugger.add_breakpoint(Breakpoint::Register(X, 16));
+        assert_eq!(
+            debugger.resume(),
+            Stop::Breakpoint(Breakpoint::Register(X, 16))
+        );
+
+        debugger.watch_x(true);
+        assert_eq!(
+            debugger.step(),
+            Stop::Watch {
+                cycle: 63,
+                old: 16,
+                new: 25
+            }
+        );
+
+        let csv = debugger.trace_csv();
+        let mut rows = csv.lines();
+        assert_eq!(rows.next(), Some("cycle,pc,instruction,x_during,x_after"));
+        assert_eq!(rows.next(), Some("1,0,addx 15,1,1"));
+        assert_eq!(rows.next(), Some("2,0,addx 15,1,16"));
+        assert_eq!(csv.lines().count(), 64);
+
+        debugger.set_trace_limit(10);
+        let csv = debugger.trace_csv();
+        assert_eq!(csv.lines().nth(1), Some("54,28,addx 21,13,13"));
+        assert_eq!(csv.lines().count(), 11);
+
+        // Breakpoints on the very first instruction stop a fresh run.
+        for breakpoint in [Breakpoint::Instruction(0), Breakpoint::Cycle(1)] {
+            let mut debugger = Debugger::new(&instructions);
+            debugger.add_breakpoint(breakpoint);
+            assert_eq!(debugger.resume(), Stop::Breakpoint(breakpoint));
+            assert_eq!(
+                debugger.state(),
+                "cycle 0 pc 0 [x=1 y=0 z=0 w=0] next: addx 15\n"
+            );
+            assert_eq!(debugger.resume(), Stop::Halted);
+        }
+        assert_eq!(Breakpoint::Cycle(1).to_string(), "cycle 1");
+
+        assert_eq!(
+            Command::parse("break x 16"),
+            Ok(Some(Command::Break(Breakpoint::Register(X, 16))))
+        );
+        assert_eq!(Command::parse("  "), Ok(None));
+        assert!(Command::parse("trace-limit 0").is_err());
+
+        let mut output = vec![];
+        Debugger::new(&instructions)
+            .run(&b"break cycle 20\nc\nfoo\nquit\nregs\n"[..], &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "(dbg) Breakpoint 0: cycle 20\n\
+             (dbg) Stopped at breakpoint `cycle 20`\n\
+             cycle 19 pc 10 [x=21 y=0 z=0 w=0] next: addx -1\n\
+             (dbg) error: unknown command `foo`\n\
+             (dbg) "
+        );
+    }
+
+    #[test]
+    fn assembler() {
+        let program = assemble(
+            "sety 5 ; count down from 5\n\
+             loop: mulx 2\n\
+             addy -1\n\
+             jmp y loop\n\
+             setz x",
+        )
+        .unwrap();
+        let y = Register(1);
+        assert_eq!(program[0], Instruction::Set(y, Operand::Value(5)));
+        assert_eq!(program[3], Instruction::Jmp(Some(y), 1));
+        assert_eq!(program[3].to_string(), "jmp y 1");
+
+        let mut cpu = Cpu::new(&program);
+        let mut cycles = 0;
+        cpu.run(|_, _| cycles += 1);
+        assert_eq!(cpu.registers, [32, 0, 32, 0]);
+        assert_eq!(cycles, 1 + 5 * (2 + 2 + 1) + 1);
+
+        // 2^40 wraps around to 0, and so does 2 * (2^31 - 1) + 2.
+        let program = assemble(
+            "sety 40\nloop: mulx 2\naddy -1\njmp y loop\n\
+             setz 2147483647\nmulz 2\naddz 2",
+        )
+        .unwrap();
+        let mut cpu = Cpu::new(&program);
+        cpu.run(|_, _| {});
+        assert_eq!(cpu.registers, [0, 0, 0, 0]);
+
+        assert_eq!(
+            assemble("noop\njmp nowhere"),
+            Err(AsmError {
+                line: 2,
+                kind: AsmErrorKind::UnknownLabel("nowhere".to_string())
+            })
+        );
+        assert!(assemble("addq 1").is_err());
+        assert!(assemble("addx 1 2").is_err());
+    }
 }
@@ -0,0 +1,86 @@
+use std::fmt::Display;
+
+use crate::crt::Crt;
+
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+/// Glyphs are separated by a blank column.
+const CELL_WIDTH: usize = GLYPH_WIDTH + 1;
+
+const FONT: [(char, [&str; GLYPH_HEIGHT]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+    (' ', ["....", "....", "....", "....", "....", "...."]),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum OcrError {
+    /// The screen isn't one row of glyphs high.
+    WrongHeight(usize),
+    /// The glyph at `index`, counting from the left, isn't in the font.
+    UnknownGlyph { index: usize, bitmap: String },
+}
+
+impl Display for OcrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WrongHeight(height) => write!(
+                f,
+                "the screen is {} pixels high instead of {}",
+                height, GLYPH_HEIGHT
+            ),
+            Self::UnknownGlyph { index, bitmap } => {
+                write!(f, "unknown glyph at position {}:\n{}", index, bitmap)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+/// The glyph starting at `column`, one row per line.
+fn glyph(crt: &Crt, column: usize) -> [String; GLYPH_HEIGHT] {
+    std::array::from_fn(|row| {
+        (column..column + GLYPH_WIDTH)
+            .map(|c| match c < crt.width() && crt.pixel(c, row) {
+                true => '#',
+                false => '.',
+            })
+            .collect()
+    })
+}
+
+/// Reads the capital letters off the screen.
+pub fn recognise(crt: &Crt) -> Result<String, OcrError> {
+    if crt.height() != GLYPH_HEIGHT {
+        return Err(OcrError::WrongHeight(crt.height()));
+    }
+
+    (0..crt.width().div_ceil(CELL_WIDTH))
+        .map(|index| {
+            let bitmap = glyph(crt, index * CELL_WIDTH);
+            FONT.iter()
+                .find(|(_, rows)| *rows == bitmap)
+                .map(|&(letter, _)| letter)
+                .ok_or_else(|| OcrError::UnknownGlyph {
+                    index,
+                    bitmap: bitmap.join("\n"),
+                })
+        })
+        .collect()
+}
@@ -0,0 +1,88 @@
+/// Cycles during which the signal strength is sampled.
+pub trait Schedule {
+    fn samples(&self, cycle: u64) -> bool;
+}
+
+/// The puzzle's schedule is `Periodic { start: 20, period: 40 }`.
+pub struct Periodic {
+    pub start: u64,
+    pub period: u64,
+}
+
+impl Schedule for Periodic {
+    fn samples(&self, cycle: u64) -> bool {
+        cycle >= self.start && (cycle - self.start).is_multiple_of(self.period)
+    }
+}
+
+impl<const N: usize> Schedule for [u64; N] {
+    fn samples(&self, cycle: u64) -> bool {
+        self.contains(&cycle)
+    }
+}
+
+impl Schedule for Vec<u64> {
+    fn samples(&self, cycle: u64) -> bool {
+        self.contains(&cycle)
+    }
+}
+
+impl<F: Fn(u64) -> bool> Schedule for F {
+    fn samples(&self, cycle: u64) -> bool {
+        self(cycle)
+    }
+}
+
+/// Parses a list of cycles `20,60,100` or a start and period `20+40`.
+pub fn parse_schedule(s: &str) -> Option<Box<dyn Fn(u64) -> bool>> {
+    if let Some((start, period)) = s.split_once('+') {
+        let schedule = Periodic {
+            start: start.parse().ok()?,
+            period: period.parse().ok().filter(|&p| p > 0)?,
+        };
+        return Some(Box::new(move |cycle| schedule.samples(cycle)));
+    }
+    let cycles = s
+        .split(',')
+        .map(|c| c.trim().parse().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    Some(Box::new(move |cycle| cycles.samples(cycle)))
+}
+
+/// Cycle, value of `x` during it and the signal strength, their product.
+pub type Sample = (u64, i32, i128);
+
+/// Collects the signal strength during a run.
+pub struct Sampler<S: Schedule> {
+    schedule: S,
+    samples: Vec<Sample>,
+    integral: i128,
+}
+
+impl<S: Schedule> Sampler<S> {
+    pub fn new(schedule: S) -> Self {
+        Self {
+            schedule,
+            samples: vec![],
+            integral: 0,
+        }
+    }
+
+    pub fn tick(&mut self, cycle: u64, x: i32) {
+        let strength = cycle as i128 * x as i128;
+        self.integral += strength;
+        if self.schedule.samples(cycle) {
+            self.samples.push((cycle, x, strength));
+        }
+    }
+
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    /// Sum of the signal strength over every cycle of the run. Long loops
+    /// with a large `x` go well past `i64`.
+    pub fn integral(&self) -> i128 {
+        self.integral
+    }
+}
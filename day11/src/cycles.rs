@@ -0,0 +1,84 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{simulation::Simulation, worry::Worry};
+
+/// Follows a single item from the start of a round for `rounds` rounds and
+/// returns how often each monkey inspects it. An item's next round only
+/// depends on which monkey holds it and its worry level, so once that pair
+/// repeats the rounds in between repeat forever and are counted at once.
+fn follow_item<W: Worry>(
+    simulation: &Simulation<W>,
+    monkey: usize,
+    level: &W::Level,
+    rounds: u64,
+) -> Vec<u64>
+where
+    W::Level: Hash + Eq,
+{
+    let mut seen = HashMap::new();
+    // Monkeys that inspected the item in every round followed so far.
+    let mut visits: Vec<Vec<usize>> = vec![];
+    let mut state = (monkey, level.clone());
+    let mut cycle_start = None;
+
+    while (visits.len() as u64) < rounds {
+        if let Some(&start) = seen.get(&state) {
+            cycle_start = Some(start);
+            break;
+        }
+        seen.insert(state.clone(), visits.len());
+
+        let (mut monkey, mut level) = state;
+        let mut round = vec![];
+        loop {
+            round.push(monkey);
+            let (target, next) = simulation.inspect(monkey, &level);
+            level = next;
+            // Monkeys further down still get to it this round.
+            let later = target > monkey;
+            monkey = target;
+            if !later {
+                break;
+            }
+        }
+        visits.push(round);
+        state = (monkey, level);
+    }
+
+    let mut inspections = vec![0; simulation.items().len()];
+    let mut count = |rounds: &[Vec<usize>], times: u64| {
+        for &monkey in rounds.iter().flatten() {
+            inspections[monkey] += times;
+        }
+    };
+    match cycle_start {
+        None => count(&visits, 1),
+        Some(start) => {
+            let cycle = &visits[start..];
+            let remaining = rounds - start as u64;
+            let length = cycle.len() as u64;
+            count(&visits[..start], 1);
+            count(cycle, remaining / length);
+            count(&cycle[..(remaining % length) as usize], 1);
+        }
+    }
+    inspections
+}
+
+/// Inspection counts after another `rounds` rounds, without simulating all
+/// of them when the items' worry levels are bounded.
+pub fn skip_rounds<W: Worry>(simulation: &Simulation<W>, rounds: u64) -> Vec<u64>
+where
+    W::Level: Hash + Eq,
+{
+    let mut inspections = simulation.inspections().to_vec();
+    for (monkey, items) in simulation.items().iter().enumerate() {
+        for item in items {
+            let counts = follow_item(simulation, monkey, item, rounds);
+            for (total, count) in inspections.iter_mut().zip(counts) {
+                *total += count;
+            }
+        }
+    }
+    inspections
+}
@@ -0,0 +1,213 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl BinOp {
+    fn precedence(self) -> u8 {
+        match self {
+            Self::Add | Self::Sub => 1,
+            Self::Mul | Self::Div => 2,
+        }
+    }
+
+    fn symbol(self) -> char {
+        match self {
+            Self::Add => '+',
+            Self::Sub => '-',
+            Self::Mul => '*',
+            Self::Div => '/',
+        }
+    }
+}
+
+/// A monkey's operation, computing the new worry level from the `old` one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Literal(i64),
+    Old,
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, BinOp, Box<Expr>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ExprError {
+    UnexpectedToken(String),
+    UnexpectedEnd,
+    InvalidNumber(String),
+    DivisionByZero,
+}
+
+impl Display for ExprError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedToken(token) => write!(f, "unexpected `{}`", token),
+            Self::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            Self::InvalidNumber(number) => write!(f, "invalid number `{}`", number),
+            Self::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+fn tokenize(s: &str) -> Vec<&str> {
+    let mut tokens = vec![];
+    let mut rest = s.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_ascii_alphanumeric() {
+            rest.find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len())
+        } else {
+            c.len_utf8()
+        };
+        tokens.push(&rest[..len]);
+        rest = rest[len..].trim_start();
+    }
+    tokens
+}
+
+/// Precedence climbing over the tokens, `+ -` bind looser than `* /` and
+/// both are left associative. Unary minus binds tightest.
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.peek();
+        self.position += 1;
+        token
+    }
+
+    fn operator(&self) -> Option<BinOp> {
+        match self.peek()? {
+            "+" => Some(BinOp::Add),
+            "-" => Some(BinOp::Sub),
+            "*" => Some(BinOp::Mul),
+            "/" => Some(BinOp::Div),
+            _ => None,
+        }
+    }
+
+    fn expression(&mut self, min_precedence: u8) -> Result<Expr, ExprError> {
+        let mut left = self.operand()?;
+        while let Some(op) = self
+            .operator()
+            .filter(|op| op.precedence() >= min_precedence)
+        {
+            self.next();
+            let right = self.expression(op.precedence() + 1)?;
+            if op == BinOp::Div && right.constant() == Some(0) {
+                return Err(ExprError::DivisionByZero);
+            }
+            left = Expr::Binary(Box::new(left), op, Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn operand(&mut self) -> Result<Expr, ExprError> {
+        match self.next().ok_or(ExprError::UnexpectedEnd)? {
+            "old" => Ok(Expr::Old),
+            "-" => Ok(Expr::Neg(Box::new(self.operand()?))),
+            "(" => {
+                let inner = self.expression(0)?;
+                match self.next() {
+                    Some(")") => Ok(inner),
+                    Some(token) => Err(ExprError::UnexpectedToken(token.to_string())),
+                    None => Err(ExprError::UnexpectedEnd),
+                }
+            }
+            token if token.starts_with(|c: char| c.is_ascii_digit()) => token
+                .parse()
+                .map(Expr::Literal)
+                .map_err(|_| ExprError::InvalidNumber(token.to_string())),
+            token => Err(ExprError::UnexpectedToken(token.to_string())),
+        }
+    }
+}
+
+impl Expr {
+    /// Parses an operation, rejecting divisions by a constant zero.
+    pub fn parse(s: &str) -> Result<Self, ExprError> {
+        let mut parser = Parser {
+            tokens: tokenize(s),
+            position: 0,
+        };
+        let expr = parser.expression(0)?;
+        match parser.peek() {
+            Some(token) => Err(ExprError::UnexpectedToken(token.to_string())),
+            None => Ok(expr),
+        }
+    }
+
+    /// Whether the expression divides anywhere.
+    pub fn divides(&self) -> bool {
+        match self {
+            Self::Neg(inner) => inner.divides(),
+            Self::Binary(left, op, right) => *op == BinOp::Div || left.divides() || right.divides(),
+            _ => false,
+        }
+    }
+
+    /// The value of an expression that doesn't depend on `old`, if it fits.
+    fn constant(&self) -> Option<i64> {
+        match self {
+            Self::Literal(value) => Some(*value),
+            Self::Old => None,
+            Self::Neg(inner) => inner.constant()?.checked_neg(),
+            Self::Binary(left, op, right) => {
+                let (left, right) = (left.constant()?, right.constant()?);
+                match op {
+                    BinOp::Add => left.checked_add(right),
+                    BinOp::Sub => left.checked_sub(right),
+                    BinOp::Mul => left.checked_mul(right),
+                    BinOp::Div => left.checked_div(right),
+                }
+            }
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Binary(_, op, _) => op.precedence(),
+            _ => u8::MAX,
+        }
+    }
+}
+
+/// Writes the expression back with only the parentheses it needs, so that
+/// parsing the text gives the same tree.
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Literal(value) => write!(f, "{}", value),
+            Self::Old => write!(f, "old"),
+            Self::Neg(inner) if inner.precedence() < u8::MAX => write!(f, "-({})", inner),
+            Self::Neg(inner) => write!(f, "-{}", inner),
+            Self::Binary(left, op, right) => {
+                if left.precedence() < op.precedence() {
+                    write!(f, "({})", left)?;
+                } else {
+                    write!(f, "{}", left)?;
+                }
+                write!(f, " {} ", op.symbol())?;
+                if right.precedence() <= op.precedence() {
+                    write!(f, "({})", right)
+                } else {
+                    write!(f, "{}", right)
+                }
+            }
+        }
+    }
+}
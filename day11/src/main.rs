@@ -1,151 +1,333 @@
-use std::cell::RefCell;
+mod cycles;
+mod expr;
+mod parser;
+mod simulation;
+mod trace;
+mod worry;
 
+use std::{env, fs, hash::Hash, process};
+
+use cycles::skip_rounds;
+
+use expr::Expr;
 use iter_tools::Itertools;
+use parser::parse_monkeys;
+use simulation::{monkey_business, Simulation, Unsupported};
+use trace::Trace;
+use worry::{Exact, Lcm, Residues, Worry};
+
+const ROUNDS: u64 = 10000;
+
+struct Options {
+    relief: Option<i64>,
+    rounds: u64,
+    skip: bool,
+    trace: bool,
+    json: Option<String>,
+}
 
+/// A monkey from the notes. Throw targets are indices into the parsed
+/// monkeys, which are ordered by their declared `id`.
 struct Monkey {
     id: usize,
     items: Vec<i64>,
-    inspections: i64,
-    operation: Operation,
+    operation: Expr,
     test: i64,
     test_pass: usize,
     test_fail: usize,
 }
 
-struct Operation {
-    op: Box<dyn Fn(i64, i64) -> i64>,
-    arg: Option<i64>,
+fn simulate<W: Worry>(
+    monkeys: &[Monkey],
+    worry: W,
+    relief: Option<i64>,
+    rounds: u64,
+) -> Result<Simulation<'_, W>, Unsupported> {
+    let mut simulation = Simulation::new(monkeys, worry, relief)?;
+    for _ in 0..rounds {
+        simulation.round();
+    }
+    Ok(simulation)
 }
 
-impl Operation {
-    fn new(func: impl Fn(i64, i64) -> i64 + 'static, arg: Option<i64>) -> Self {
-        Self {
-            op: Box::new(func),
-            arg,
+fn report(inspections: Result<Vec<u64>, Unsupported>) {
+    match inspections {
+        Ok(inspections) => {
+            println!("Inspections: {:?}", inspections);
+            println!("Monkey business: {}", monkey_business(&inspections));
         }
+        Err(e) => eprintln!("{}", e),
     }
+}
 
-    fn call(&mut self, old: i64) -> i64 {
-        (self.op)(old.clone(), self.arg.clone().unwrap_or(old))
+/// Runs the rounds one by one, recording them when tracing, or with `skip`
+/// follows items until their worry levels repeat.
+fn run<W: Worry>(monkeys: &[Monkey], worry: W, options: &Options) -> Result<Vec<u64>, Unsupported>
+where
+    W::Level: Hash + Eq,
+{
+    if options.skip {
+        let simulation = Simulation::new(monkeys, worry, options.relief)?;
+        return Ok(skip_rounds(&simulation, options.rounds));
     }
+    if options.trace || options.json.is_some() {
+        let mut simulation = Simulation::new(monkeys, worry, options.relief)?;
+        let trace = Trace::record(&mut simulation, options.rounds);
+        if options.trace {
+            print!("{}", trace.summary());
+        }
+        if let Some(path) = &options.json {
+            fs::write(path, trace.to_json()).expect("Failed to write trace");
+        }
+        return Ok(simulation.inspections().to_vec());
+    }
+    simulate(monkeys, worry, options.relief, options.rounds).map(|s| s.inspections().to_vec())
 }
 
-fn parse_expression(s: &str) -> Operation {
-    let parts = s.split(" ").collect::<Vec<_>>();
-
-    assert_eq!(parts.len(), 5);
-    assert_eq!(parts[0], "new");
-    assert_eq!(parts[1], "=");
-    assert_eq!(parts[2], "old");
-
-    let arg = parts[4].parse::<i64>().map_or(None, |i| Some(i));
+fn main() {
+    let mut backend = None;
+    let mut options = Options {
+        relief: None,
+        rounds: ROUNDS,
+        skip: false,
+        trace: false,
+        json: None,
+    };
 
-    match parts[3] {
-        "+" => Operation::new(|old, arg| old + arg, arg),
-        "*" => Operation::new(|old, arg| old * arg, arg),
-        "-" => Operation::new(|old, arg| old - arg, arg),
-        _ => panic!("Wrong operation HELP"),
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--backend" => backend = Some(args.next().expect("Missing worry backend")),
+            "--rounds" => {
+                let n = args.next().expect("Missing round count");
+                options.rounds = n.parse().expect("Invalid round count");
+            }
+            "--relief" => {
+                let n = args.next().expect("Missing relief divisor");
+                let relief = n.parse().ok().filter(|&n| n > 0);
+                options.relief = Some(relief.expect("Relief divisor must be a positive number"));
+            }
+            "--skip" => options.skip = true,
+            "--trace" => options.trace = true,
+            "--json" => options.json = Some(args.next().expect("Missing trace path")),
+            _ => panic!("Unknown argument {}", arg),
+        }
     }
-}
 
-fn parse_monkey(lines: &[&str]) -> Monkey {
-    let id = lines[0]
-        .replace("Monkey ", "")
-        .replace(":", "")
-        .parse::<usize>()
-        .expect("Invalid id format");
-    let items = lines[1]
-        .trim()
-        .replace("Starting items: ", "")
-        .split(", ")
-        .flat_map(&str::parse::<i64>)
-        .collect::<Vec<_>>();
-    let operation = parse_expression(lines[2].trim().replace("Operation: ", "").as_str());
-    let test = lines[3]
-        .trim()
-        .replace("Test: divisible by ", "")
-        .parse::<i64>()
-        .expect("Unexpected test format");
-    let test_pass = lines[4]
-        .trim()
-        .replace("If true: throw to monkey ", "")
-        .parse::<usize>()
-        .expect("Wrong monkey throw format");
-    let test_fail = lines[5]
-        .trim()
-        .replace("If false: throw to monkey ", "")
-        .parse::<usize>()
-        .expect("Wrong monkey throw format");
-
-    Monkey {
-        id,
-        items,
-        operation,
-        test,
-        test_pass,
-        test_fail,
-        inspections: 0,
+    let monkeys = parse_monkeys(include_str!("input")).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    let divisors = monkeys.iter().map(|m| m.test).collect_vec();
+    let lcm = Lcm::new(&divisors);
+    // Without a choice, levels are only kept whole when they're divided, and
+    // kept as residues when their least common multiple is too large.
+    let backend = backend.unwrap_or_else(|| {
+        let divides = options.relief.is_some() || monkeys.iter().any(|m| m.operation.divides());
+        match (divides, &lcm) {
+            (true, _) => "exact",
+            (false, Ok(_)) => "lcm",
+            (false, Err(_)) => "residues",
+        }
+        .to_string()
+    });
+    match backend.as_str() {
+        "exact" => report(run(&monkeys, Exact::new(&divisors), &options)),
+        "lcm" => match lcm {
+            Ok(lcm) => report(run(&monkeys, lcm, &options)),
+            Err(e) => eprintln!("{}", e),
+        },
+        "residues" => report(run(&monkeys, Residues::new(&divisors), &options)),
+        _ => panic!("Unknown worry backend {}", backend),
     }
 }
 
-fn simulate_round(monkeys: &mut Vec<RefCell<Monkey>>, simulate_worry: bool) {
-    let modulo = monkeys.iter().fold(1, |acc, x| acc * x.borrow().test);
-    for i in 0..monkeys.len() {
-        simulate_monkey(monkeys, i, simulate_worry, modulo);
+#[cfg(test)]
+mod tests {
+    use crate::{expr::ExprError, parser::ParseErrorKind, worry::LcmError, *};
+
+    #[test]
+    fn expressions() {
+        let exact = Exact::new(&[]);
+        for (text, old, value) in [
+            ("old * old", 3, 9),
+            ("old + 2 * 3", 1, 7),
+            ("(old + 2) * 3", 1, 9),
+            ("old - 4 - 2", 10, 4),
+            ("old - (4 - 2)", 10, 8),
+            ("old / 2 + old * (old - 1)", 7, 45),
+            ("-old * 2", 3, -6),
+            ("old - -(old - 1)", 5, 9),
+            ("-(old + 1) * 2", 5, -12),
+        ] {
+            let expr = Expr::parse(text).unwrap();
+            assert_eq!(exact.eval(&expr, &exact.level(old)), exact.level(value));
+            assert_eq!(expr.to_string(), text);
+        }
+        assert_eq!(Expr::parse("((old))*  19").unwrap().to_string(), "old * 19");
+        assert!(Expr::parse("old *").is_err());
+        assert!(Expr::parse("old + (1").is_err());
+        assert!(Expr::parse("old 1").is_err());
+        assert_eq!(Expr::parse("old / 0"), Err(ExprError::DivisionByZero));
+        assert_eq!(Expr::parse("old / (3 - 3)"), Err(ExprError::DivisionByZero));
+        assert_eq!(Expr::parse("old / -1").unwrap().to_string(), "old / -1");
+        assert_eq!(Expr::parse("-(-old)").unwrap().to_string(), "--old");
     }
-}
 
-fn simulate_monkey(monkeys: &mut Vec<RefCell<Monkey>>, monkey: usize, simulate_worry: bool, modulo: i64) {    
-    let mut current_monkey = monkeys[monkey].borrow_mut();
+    #[test]
+    fn backends() {
+        let monkeys = parse_monkeys(include_str!("example")).unwrap();
+        let divisors = monkeys.iter().map(|m| m.test).collect_vec();
 
-    let items = current_monkey.items.clone();
-    current_monkey.items.clear();
+        let exact = simulate(&monkeys, Exact::new(&divisors), Some(3), 20).unwrap();
+        assert_eq!(monkey_business(exact.inspections()), 10605);
+        assert!(matches!(
+            simulate(&monkeys, Lcm::new(&divisors).unwrap(), Some(3), 20),
+            Err(Unsupported)
+        ));
 
-    for mut item in items {
-        item = current_monkey.operation.call(item);
-        if simulate_worry {
-            item = item / 3;
+        let mut exact = Simulation::new(&monkeys, Exact::new(&divisors), None).unwrap();
+        let mut lcm = Simulation::new(&monkeys, Lcm::new(&divisors).unwrap(), None).unwrap();
+        let mut residues = Simulation::new(&monkeys, Residues::new(&divisors), None).unwrap();
+        for _ in 0..20 {
+            exact.round();
+            lcm.round();
+            residues.round();
+            assert_eq!(exact.inspections(), lcm.inspections());
+            assert_eq!(lcm.inspections(), residues.inspections());
         }
 
-        item = item % modulo;
+        let lcm = simulate(&monkeys, Lcm::new(&divisors).unwrap(), None, 10000).unwrap();
+        let residues = simulate(&monkeys, Residues::new(&divisors), None, 10000).unwrap();
+        assert_eq!(monkey_business(lcm.inspections()), 2713310158);
+        assert_eq!(monkey_business(residues.inspections()), 2713310158);
 
-        current_monkey.inspections += 1;
+        let exact = Exact::new(&divisors);
+        let expr = Expr::parse("old / (old - 79)").unwrap();
+        assert_eq!(exact.eval(&expr, &exact.level(79)), exact.level(0));
+        assert_eq!(exact.eval(&expr, &exact.level(158)), exact.level(2));
+    }
 
-        if &item % current_monkey.test == i64::from(0) {
-            monkeys[current_monkey.test_pass]
-                .borrow_mut()
-                .items
-                .push(item.clone());
-        } else {
-            monkeys[current_monkey.test_fail]
-                .borrow_mut()
-                .items
-                .push(item.clone());
+    #[test]
+    fn lcm_limits() {
+        assert_eq!(Lcm::new(&[4, 6, 10]).unwrap().level(61), 1);
+        assert_eq!(Lcm::new(&[3, 0]).err(), Some(LcmError::ZeroDivisor));
+        // Primes just below 2^31, two of them fit in an i64 and repeating one
+        // keeps the multiple, but a third one overflows.
+        let primes = [2147483647, 2147483629, 2147483587];
+        assert!(Lcm::new(&[primes[0], primes[1], primes[0]]).is_ok());
+        assert_eq!(Lcm::new(&primes).err(), Some(LcmError::Overflow));
+
+        // Residues still report those levels, five such tests are past i128.
+        let residues = Residues::new(&[2147483647, 2147483629, 2147483587, 2147483579, 2147483563]);
+        let cube = Expr::parse("old * old * old").unwrap();
+        let cube = residues.eval(&cube, &residues.level(i64::MAX));
+        assert_eq!(
+            residues.value(&cube),
+            "6064767243372415809528532327887213967025575"
+        );
+        assert_eq!(residues.value(&residues.level(61)), "61");
+    }
+
+    #[test]
+    fn skipping_rounds() {
+        let monkeys = parse_monkeys(include_str!("example")).unwrap();
+        let divisors = monkeys.iter().map(|m| m.test).collect_vec();
+
+        for rounds in [0, 1, 20, 1000, 10000] {
+            let stepped = simulate(&monkeys, Lcm::new(&divisors).unwrap(), None, rounds).unwrap();
+            let start = Simulation::new(&monkeys, Lcm::new(&divisors).unwrap(), None).unwrap();
+            assert_eq!(skip_rounds(&start, rounds), stepped.inspections());
         }
+
+        // Exact levels work too, they just rarely repeat.
+        let exact = Simulation::new(&monkeys, Exact::new(&divisors), Some(3)).unwrap();
+        let stepped = simulate(&monkeys, Exact::new(&divisors), Some(3), 20).unwrap();
+        assert_eq!(skip_rounds(&exact, 20), stepped.inspections());
+
+        let halfway = simulate(&monkeys, Residues::new(&divisors), None, 5000).unwrap();
+        assert_eq!(monkey_business(&skip_rounds(&halfway, 5000)), 2713310158);
     }
-}
 
-fn main() {
-    let mut monkeys = include_str!("input")
-        .lines()
-        .filter(|line| !line.trim().is_empty())
-        .chunks(6)
-        .into_iter()
-        .map(|chunk| {
-            let lines = chunk.collect_vec().into_boxed_slice();
-
-            RefCell::new(parse_monkey(&lines))
-        })
-        .collect_vec();
-    for _ in 0..10000 {
-        simulate_round(&mut monkeys, false);
+    #[test]
+    fn parsing() {
+        let notes = "Monkey 7:\n\
+                     If false: throw to monkey 3\n\
+                     Test: divisible by 5\n\
+                     Operation: new = old * 2\n\
+                     Starting items:\n\
+                     If true: throw to monkey 3\n\
+                     \n\
+                     Monkey 3:\n\
+                     Starting items: 4, 6\n\
+                     Operation: new = old + 1\n\
+                     Test: divisible by 2\n\
+                     If true: throw to monkey 7\n\
+                     If false: throw to monkey 7\n";
+        let monkeys = parse_monkeys(notes).unwrap();
+        assert_eq!(monkeys.iter().map(|m| m.id).collect_vec(), [3, 7]);
+        assert_eq!(monkeys[0].items, [4, 6]);
+        assert!(monkeys[1].items.is_empty());
+        assert_eq!((monkeys[0].test_pass, monkeys[1].test_fail), (1, 0));
+
+        let error = |notes: &str| parse_monkeys(notes).err().map(|e| (e.line, e.kind));
+        assert_eq!(
+            error(&notes.replace("monkey 7", "monkey 3")),
+            Some((12, ParseErrorKind::SelfThrow(3)))
+        );
+        assert_eq!(
+            error(&notes.replace("If false: throw to monkey 3", "If false: throw to monkey 4")),
+            Some((2, ParseErrorKind::UnknownTarget(4)))
+        );
+        assert_eq!(
+            error(&notes.replace("Starting items: 4, 6", "Starting items: 4, x")),
+            Some((9, ParseErrorKind::InvalidItem("x".to_string())))
+        );
+        assert_eq!(
+            error(&notes.replace("Test: divisible by 2\n", "")),
+            Some((8, ParseErrorKind::MissingAttribute("Test")))
+        );
+        assert_eq!(
+            error(&notes.replace("Monkey 3", "Monkey 7")),
+            Some((8, ParseErrorKind::DuplicateId(7)))
+        );
+        assert!(parse_monkeys("Starting items: 1").is_err());
+    }
+
+    #[test]
+    fn tracing() {
+        let monkeys = parse_monkeys(include_str!("example")).unwrap();
+        let divisors = monkeys.iter().map(|m| m.test).collect_vec();
+
+        let mut exact = Simulation::new(&monkeys, Exact::new(&divisors), Some(3)).unwrap();
+        let trace = Trace::record(&mut exact, 2);
+        let summary = trace.summary();
+        let mut lines = summary.lines();
+        assert_eq!(
+            lines.next(),
+            Some("After round 1, the monkeys are holding items with these worry levels:")
+        );
+        assert_eq!(lines.next(), Some("Monkey 0: 20, 23, 27, 26"));
+        assert_eq!(
+            lines.next(),
+            Some("Monkey 1: 2080, 25, 167, 207, 401, 1046")
+        );
+        assert_eq!(lines.next(), Some("Monkey 2: "));
+        assert!(summary.contains("== After round 1 ==\nMonkey 0 inspected items 2 times.\n"));
+
+        let throw = &trace.rounds[0].throws[0];
+        assert_eq!((throw.from, throw.to), (0, 3));
+        assert_eq!((throw.before.as_str(), throw.after.as_str()), ("79", "500"));
+        assert!(trace.to_json().starts_with(
+            r#"{"monkeys":[0,1,2,3],"rounds":[{"round":1,"throws":[{"from":0,"to":3,"before":79,"after":500},"#
+        ));
+
+        let mut lcm = Simulation::new(&monkeys, Lcm::new(&divisors).unwrap(), None).unwrap();
+        let mut residues = Simulation::new(&monkeys, Residues::new(&divisors), None).unwrap();
+        let lcm = Trace::record(&mut lcm, 20);
+        let residues = Trace::record(&mut residues, 20);
+        assert_eq!(lcm.rounds[0].inspections, [2, 4, 3, 6]);
+        assert_eq!(lcm.summary(), residues.summary());
+        assert_eq!(lcm.to_json(), residues.to_json());
     }
-    let monkey_business = monkeys
-        .iter()
-        .map(|m| m.borrow().inspections)
-        .sorted()
-        .rev()
-        .take(2)
-        .fold(1i64, |acc, x| acc * x);
-    println!("Monkey business: {}", monkey_business);
 }
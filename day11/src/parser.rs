@@ -0,0 +1,214 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    expr::{Expr, ExprError},
+    Monkey,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum ParseErrorKind {
+    MissingHeader(String),
+    InvalidId(String),
+    DuplicateId(usize),
+    UnknownAttribute(String),
+    DuplicateAttribute(&'static str),
+    MissingAttribute(&'static str),
+    MalformedAttribute(&'static str, String),
+    InvalidItem(String),
+    InvalidOperation(ExprError),
+    ZeroDivisor,
+    UnknownTarget(usize),
+    SelfThrow(usize),
+}
+
+/// A problem with the notes. `line` counts from 1 and points at the offending
+/// line, or at the block's header when an attribute is missing.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingHeader(line) => write!(f, "expected `Monkey N:`, found `{}`", line),
+            Self::InvalidId(id) => write!(f, "invalid monkey id `{}`", id),
+            Self::DuplicateId(id) => write!(f, "monkey {} is described twice", id),
+            Self::UnknownAttribute(name) => write!(f, "unknown attribute `{}`", name),
+            Self::DuplicateAttribute(name) => write!(f, "`{}` is given twice", name),
+            Self::MissingAttribute(name) => write!(f, "the monkey is missing `{}`", name),
+            Self::MalformedAttribute(name, value) => {
+                write!(f, "malformed `{}` value `{}`", name, value)
+            }
+            Self::InvalidItem(item) => write!(f, "invalid worry level `{}`", item),
+            Self::InvalidOperation(e) => write!(f, "invalid operation, {}", e),
+            Self::ZeroDivisor => write!(f, "the test can't divide by zero"),
+            Self::UnknownTarget(id) => write!(f, "there's no monkey {} to throw to", id),
+            Self::SelfThrow(id) => write!(f, "monkey {} throws to itself", id),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+const STARTING_ITEMS: &str = "Starting items";
+const OPERATION: &str = "Operation";
+const TEST: &str = "Test";
+const IF_TRUE: &str = "If true";
+const IF_FALSE: &str = "If false";
+
+/// A block's attributes so far. Throw targets are still declared ids, kept
+/// with the line they were given on.
+#[derive(Default)]
+struct Notes {
+    items: Option<Vec<i64>>,
+    operation: Option<Expr>,
+    test: Option<i64>,
+    test_pass: Option<(usize, usize)>,
+    test_fail: Option<(usize, usize)>,
+}
+
+fn set<T>(slot: &mut Option<T>, name: &'static str, value: T) -> Result<(), ParseErrorKind> {
+    match slot.replace(value) {
+        Some(_) => Err(ParseErrorKind::DuplicateAttribute(name)),
+        None => Ok(()),
+    }
+}
+
+fn strip<'a>(name: &'static str, value: &'a str, prefix: &str) -> Result<&'a str, ParseErrorKind> {
+    value
+        .strip_prefix(prefix)
+        .map(str::trim)
+        .ok_or_else(|| ParseErrorKind::MalformedAttribute(name, value.to_string()))
+}
+
+fn number<T: std::str::FromStr>(name: &'static str, value: &str) -> Result<T, ParseErrorKind> {
+    value
+        .parse()
+        .map_err(|_| ParseErrorKind::MalformedAttribute(name, value.to_string()))
+}
+
+fn parse_attribute(
+    notes: &mut Notes,
+    line: &str,
+    line_number: usize,
+) -> Result<(), ParseErrorKind> {
+    let (name, value) = line
+        .split_once(':')
+        .ok_or_else(|| ParseErrorKind::UnknownAttribute(line.to_string()))?;
+    let value = value.trim();
+    match name.trim() {
+        STARTING_ITEMS => {
+            let items = value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| {
+                    item.parse()
+                        .map_err(|_| ParseErrorKind::InvalidItem(item.to_string()))
+                })
+                .collect::<Result<_, _>>()?;
+            set(&mut notes.items, STARTING_ITEMS, items)
+        }
+        OPERATION => {
+            let expr = strip(OPERATION, value, "new =")?;
+            let expr = Expr::parse(expr).map_err(ParseErrorKind::InvalidOperation)?;
+            set(&mut notes.operation, OPERATION, expr)
+        }
+        TEST => match number(TEST, strip(TEST, value, "divisible by")?)? {
+            0 => Err(ParseErrorKind::ZeroDivisor),
+            divisor => set(&mut notes.test, TEST, divisor),
+        },
+        name @ (IF_TRUE | IF_FALSE) => {
+            let (name, slot) = match name {
+                IF_TRUE => (IF_TRUE, &mut notes.test_pass),
+                _ => (IF_FALSE, &mut notes.test_fail),
+            };
+            let target = number(name, strip(name, value, "throw to monkey")?)?;
+            set(slot, name, (target, line_number))
+        }
+        name => Err(ParseErrorKind::UnknownAttribute(name.to_string())),
+    }
+}
+
+/// Parses the notes into monkeys ordered by id. Blocks are separated by
+/// blank lines and start with `Monkey N:`, their attributes can come in any
+/// order. Throw targets are resolved to indices into the result.
+pub fn parse_monkeys(s: &str) -> Result<Vec<Monkey>, ParseError> {
+    // Declared id -> header line and notes.
+    let mut blocks: HashMap<usize, (usize, Notes)> = HashMap::new();
+    let mut current = None;
+
+    for (index, line) in s.lines().enumerate() {
+        let error = |kind| ParseError {
+            line: index + 1,
+            kind,
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            current = None;
+            continue;
+        }
+
+        match current {
+            Some(id) => {
+                let (_, notes) = blocks.get_mut(&id).unwrap();
+                parse_attribute(notes, line, index + 1).map_err(error)?;
+            }
+            None => {
+                let id = line
+                    .strip_prefix("Monkey ")
+                    .and_then(|rest| rest.strip_suffix(':'))
+                    .ok_or_else(|| error(ParseErrorKind::MissingHeader(line.to_string())))?;
+                let id = id
+                    .trim()
+                    .parse()
+                    .map_err(|_| error(ParseErrorKind::InvalidId(id.to_string())))?;
+                if blocks.insert(id, (index + 1, Notes::default())).is_some() {
+                    return Err(error(ParseErrorKind::DuplicateId(id)));
+                }
+                current = Some(id);
+            }
+        }
+    }
+
+    let mut ids = blocks.keys().copied().collect::<Vec<_>>();
+    ids.sort_unstable();
+    let position = |id, target: (usize, usize)| {
+        let (target, line) = target;
+        let kind = if target == id {
+            ParseErrorKind::SelfThrow(id)
+        } else {
+            match ids.binary_search(&target) {
+                Ok(position) => return Ok(position),
+                Err(_) => ParseErrorKind::UnknownTarget(target),
+            }
+        };
+        Err(ParseError { line, kind })
+    };
+
+    ids.iter()
+        .map(|&id| {
+            let (line, notes) = blocks.remove(&id).unwrap();
+            let missing = |name| ParseError {
+                line,
+                kind: ParseErrorKind::MissingAttribute(name),
+            };
+            Ok(Monkey {
+                id,
+                items: notes.items.ok_or_else(|| missing(STARTING_ITEMS))?,
+                operation: notes.operation.ok_or_else(|| missing(OPERATION))?,
+                test: notes.test.ok_or_else(|| missing(TEST))?,
+                test_pass: position(id, notes.test_pass.ok_or_else(|| missing(IF_TRUE))?)?,
+                test_fail: position(id, notes.test_fail.ok_or_else(|| missing(IF_FALSE))?)?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,105 @@
+use std::{fmt::Display, mem};
+
+use crate::{worry::Worry, Monkey};
+
+/// The worry backend can't divide, but relief or an operation needs it.
+#[derive(Debug, PartialEq)]
+pub struct Unsupported;
+
+impl Display for Unsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "this worry backend can't divide worry levels")
+    }
+}
+
+impl std::error::Error for Unsupported {}
+
+/// Monkeys passing items around, with worry levels kept by `W`.
+pub struct Simulation<'a, W: Worry> {
+    monkeys: &'a [Monkey],
+    worry: W,
+    relief: Option<i64>,
+    items: Vec<Vec<W::Level>>,
+    inspections: Vec<u64>,
+}
+
+impl<'a, W: Worry> Simulation<'a, W> {
+    /// Starts from the monkeys' starting items. With `relief` worry levels
+    /// are divided by it after every inspection.
+    pub fn new(monkeys: &'a [Monkey], worry: W, relief: Option<i64>) -> Result<Self, Unsupported> {
+        let divides = relief.is_some() || monkeys.iter().any(|m| m.operation.divides());
+        if divides && !worry.exact() {
+            return Err(Unsupported);
+        }
+
+        let items = monkeys
+            .iter()
+            .map(|m| m.items.iter().map(|&i| worry.level(i)).collect())
+            .collect();
+        Ok(Self {
+            monkeys,
+            worry,
+            relief,
+            items,
+            inspections: vec![0; monkeys.len()],
+        })
+    }
+
+    /// The monkey at `monkey` inspects an item, returns where it throws it
+    /// and the item's new worry level.
+    pub fn inspect(&self, monkey: usize, item: &W::Level) -> (usize, W::Level) {
+        let definition = &self.monkeys[monkey];
+        let mut item = self.worry.eval(&definition.operation, item);
+        if let Some(by) = self.relief {
+            item = self.worry.relieve(&item, by);
+        }
+
+        let target = if self.worry.divisible(&item, monkey) {
+            definition.test_pass
+        } else {
+            definition.test_fail
+        };
+        (target, item)
+    }
+
+    pub fn round(&mut self) {
+        self.round_with(|_, _, _, _, _| {});
+    }
+
+    /// Plays a round, calling `on_throw` with the backend, both monkeys and
+    /// the item's level before and after the inspection for every throw.
+    pub fn round_with(&mut self, mut on_throw: impl FnMut(&W, usize, usize, &W::Level, &W::Level)) {
+        for i in 0..self.monkeys.len() {
+            for before in mem::take(&mut self.items[i]) {
+                let (target, item) = self.inspect(i, &before);
+                on_throw(&self.worry, i, target, &before, &item);
+                self.inspections[i] += 1;
+                self.items[target].push(item);
+            }
+        }
+    }
+
+    pub fn monkeys(&self) -> &[Monkey] {
+        self.monkeys
+    }
+
+    pub fn worry(&self) -> &W {
+        &self.worry
+    }
+
+    /// Items each monkey holds, in the order it will inspect them.
+    pub fn items(&self) -> &[Vec<W::Level>] {
+        &self.items
+    }
+
+    pub fn inspections(&self) -> &[u64] {
+        &self.inspections
+    }
+}
+
+/// Product of the two highest inspection counts.
+pub fn monkey_business(inspections: &[u64]) -> u128 {
+    let mut inspections = inspections.to_vec();
+    inspections.sort_unstable_by(|a, b| b.cmp(a));
+    inspections.iter().take(2).map(|&i| i as u128).product()
+}
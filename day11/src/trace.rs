@@ -0,0 +1,135 @@
+use std::fmt::Write;
+
+use crate::{simulation::Simulation, worry::Worry};
+
+/// A throw between monkeys, given by their declared ids.
+pub struct Throw {
+    pub from: usize,
+    pub to: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// Everything that happened in a round, worry levels written as numbers.
+pub struct Round {
+    pub number: u64,
+    pub throws: Vec<Throw>,
+    /// Items every monkey holds at the end of the round.
+    pub holding: Vec<Vec<String>>,
+    /// Inspections of every monkey so far.
+    pub inspections: Vec<u64>,
+}
+
+pub struct Trace {
+    /// Declared ids of the monkeys, in the order rounds list them.
+    pub ids: Vec<usize>,
+    pub rounds: Vec<Round>,
+}
+
+impl Trace {
+    /// Plays `rounds` rounds, recording each of them.
+    pub fn record<W: Worry>(simulation: &mut Simulation<W>, rounds: u64) -> Self {
+        let ids = simulation
+            .monkeys()
+            .iter()
+            .map(|m| m.id)
+            .collect::<Vec<_>>();
+        let mut trace = Self {
+            ids: ids.clone(),
+            rounds: vec![],
+        };
+        for number in 1..=rounds {
+            let mut throws = vec![];
+            simulation.round_with(|worry, from, to, before, after| {
+                throws.push(Throw {
+                    from: ids[from],
+                    to: ids[to],
+                    before: worry.value(before),
+                    after: worry.value(after),
+                })
+            });
+
+            let worry = simulation.worry();
+            trace.rounds.push(Round {
+                number,
+                throws,
+                holding: simulation
+                    .items()
+                    .iter()
+                    .map(|items| items.iter().map(|i| worry.value(i)).collect())
+                    .collect(),
+                inspections: simulation.inspections().to_vec(),
+            });
+        }
+        trace
+    }
+
+    /// The puzzle's summaries of what the monkeys hold and how often they
+    /// inspected items after every round.
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        for round in &self.rounds {
+            writeln!(
+                out,
+                "After round {}, the monkeys are holding items with these worry levels:",
+                round.number
+            )
+            .unwrap();
+            for (id, items) in self.ids.iter().zip(&round.holding) {
+                writeln!(out, "Monkey {}: {}", id, items.join(", ")).unwrap();
+            }
+            writeln!(out).unwrap();
+
+            writeln!(out, "== After round {} ==", round.number).unwrap();
+            for (id, count) in self.ids.iter().zip(&round.inspections) {
+                writeln!(out, "Monkey {} inspected items {} times.", id, count).unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+        out
+    }
+
+    /// The trace as JSON, worry levels being numbers of any size.
+    pub fn to_json(&self) -> String {
+        let list = |items: &[String]| format!("[{}]", items.join(","));
+        let rounds = self
+            .rounds
+            .iter()
+            .map(|round| {
+                let throws = round
+                    .throws
+                    .iter()
+                    .map(|t| {
+                        format!(
+                            r#"{{"from":{},"to":{},"before":{},"after":{}}}"#,
+                            t.from, t.to, t.before, t.after
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let holding = round
+                    .holding
+                    .iter()
+                    .map(|items| list(items))
+                    .collect::<Vec<_>>();
+                let inspections = round
+                    .inspections
+                    .iter()
+                    .map(|i| i.to_string())
+                    .collect::<Vec<_>>();
+                format!(
+                    r#"{{"round":{},"throws":{},"holding":{},"inspections":{}}}"#,
+                    round.number,
+                    list(&throws),
+                    list(&holding),
+                    list(&inspections)
+                )
+            })
+            .collect::<Vec<_>>();
+        let ids = self.ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        format!(
+            "{{\"monkeys\":{},\"rounds\":{}}}\n",
+            list(&ids),
+            list(&rounds)
+        )
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt::Display;
+
+use num_bigint::BigInt;
+
+use crate::expr::{BinOp, Expr};
+
+/// Arithmetic on worry levels. Backends only need to keep enough of a level
+/// to tell which monkeys' tests it passes.
+pub trait Worry {
+    type Level: Clone;
+
+    /// Whether levels are kept whole, so they can be divided.
+    fn exact(&self) -> bool {
+        false
+    }
+
+    fn level(&self, value: i64) -> Self::Level;
+
+    /// Combines two levels, division is only called on exact backends.
+    /// Dividing by zero gives zero, the parser only rejects constant zero
+    /// divisors and `old / (old - 79)` shouldn't stop a round at item 79.
+    fn combine(&self, op: BinOp, left: &Self::Level, right: &Self::Level) -> Self::Level;
+
+    /// Divides a level after an inspection, only called on exact backends.
+    fn relieve(&self, level: &Self::Level, by: i64) -> Self::Level;
+
+    /// Whether the level passes the test of the monkey at `test`.
+    fn divisible(&self, level: &Self::Level, test: usize) -> bool;
+
+    /// The level as a number, reduced like the backend reduces it.
+    fn value(&self, level: &Self::Level) -> String;
+
+    fn eval(&self, expr: &Expr, old: &Self::Level) -> Self::Level {
+        match expr {
+            Expr::Literal(value) => self.level(*value),
+            Expr::Old => old.clone(),
+            Expr::Neg(inner) => self.combine(BinOp::Sub, &self.level(0), &self.eval(inner, old)),
+            Expr::Binary(left, op, right) => {
+                self.combine(*op, &self.eval(left, old), &self.eval(right, old))
+            }
+        }
+    }
+}
+
+/// Whole worry levels, never reduced. They grow quickly without relief.
+pub struct Exact {
+    divisors: Vec<BigInt>,
+}
+
+impl Exact {
+    pub fn new(divisors: &[i64]) -> Self {
+        Self {
+            divisors: divisors.iter().map(|&d| BigInt::from(d)).collect(),
+        }
+    }
+}
+
+impl Worry for Exact {
+    type Level = BigInt;
+
+    fn exact(&self) -> bool {
+        true
+    }
+
+    fn level(&self, value: i64) -> BigInt {
+        BigInt::from(value)
+    }
+
+    fn combine(&self, op: BinOp, left: &BigInt, right: &BigInt) -> BigInt {
+        match op {
+            BinOp::Add => left + right,
+            BinOp::Sub => left - right,
+            BinOp::Mul => left * right,
+            BinOp::Div if *right == BigInt::ZERO => BigInt::ZERO,
+            BinOp::Div => left / right,
+        }
+    }
+
+    fn relieve(&self, level: &BigInt, by: i64) -> BigInt {
+        level / by
+    }
+
+    fn divisible(&self, level: &BigInt, test: usize) -> bool {
+        (level % &self.divisors[test]) == BigInt::ZERO
+    }
+
+    fn value(&self, level: &BigInt) -> String {
+        level.to_string()
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Returns `(g, x, y)` with `a * x + b * y == g`, the gcd of `a` and `b`.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - a / b * y)
+    }
+}
+
+/// The tests' divisors can't be used for `Lcm` levels.
+#[derive(Debug, PartialEq)]
+pub enum LcmError {
+    ZeroDivisor,
+    /// The least common multiple doesn't fit in an `i64`.
+    Overflow,
+}
+
+impl Display for LcmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ZeroDivisor => write!(f, "a test divides by zero"),
+            Self::Overflow => write!(f, "the least common multiple of the tests is too large"),
+        }
+    }
+}
+
+impl std::error::Error for LcmError {}
+
+/// Levels modulo the least common multiple of all the tests, which keeps
+/// every test's outcome.
+pub struct Lcm {
+    modulus: i64,
+    divisors: Vec<i64>,
+}
+
+impl Lcm {
+    pub fn new(divisors: &[i64]) -> Result<Self, LcmError> {
+        let mut modulus = 1i64;
+        for &d in divisors {
+            if d == 0 {
+                return Err(LcmError::ZeroDivisor);
+            }
+            let d = d.abs();
+            modulus = (modulus / gcd(modulus, d))
+                .checked_mul(d)
+                .ok_or(LcmError::Overflow)?;
+        }
+        Ok(Self {
+            modulus,
+            divisors: divisors.to_vec(),
+        })
+    }
+}
+
+impl Worry for Lcm {
+    type Level = i64;
+
+    fn level(&self, value: i64) -> i64 {
+        value.rem_euclid(self.modulus)
+    }
+
+    fn combine(&self, op: BinOp, &left: &i64, &right: &i64) -> i64 {
+        let (left, right, modulus) = (left as i128, right as i128, self.modulus as i128);
+        let value = match op {
+            BinOp::Add => left + right,
+            BinOp::Sub => left - right,
+            BinOp::Mul => left * right,
+            BinOp::Div => unreachable!("levels modulo the tests can't be divided"),
+        };
+        value.rem_euclid(modulus) as i64
+    }
+
+    fn relieve(&self, _: &i64, _: i64) -> i64 {
+        unreachable!("levels modulo the tests can't be divided")
+    }
+
+    fn divisible(&self, level: &i64, test: usize) -> bool {
+        level % self.divisors[test] == 0
+    }
+
+    fn value(&self, level: &i64) -> String {
+        level.to_string()
+    }
+}
+
+/// Levels as their remainders for every test separately, the Chinese
+/// remainder theorem representation of `Lcm`'s levels.
+pub struct Residues {
+    divisors: Vec<i64>,
+}
+
+impl Residues {
+    pub fn new(divisors: &[i64]) -> Self {
+        Self {
+            divisors: divisors.to_vec(),
+        }
+    }
+}
+
+impl Worry for Residues {
+    type Level = Vec<i64>;
+
+    fn level(&self, value: i64) -> Vec<i64> {
+        self.divisors.iter().map(|&d| value.rem_euclid(d)).collect()
+    }
+
+    fn combine(&self, op: BinOp, left: &Vec<i64>, right: &Vec<i64>) -> Vec<i64> {
+        let combine = |a: i64, b: i64, d: i64| {
+            let (a, b, d) = (a as i128, b as i128, d as i128);
+            let value = match op {
+                BinOp::Add => a + b,
+                BinOp::Sub => a - b,
+                BinOp::Mul => a * b,
+                BinOp::Div => unreachable!("remainders can't be divided"),
+            };
+            value.rem_euclid(d) as i64
+        };
+        left.iter()
+            .zip(right)
+            .zip(&self.divisors)
+            .map(|((&a, &b), &d)| combine(a, b, d))
+            .collect()
+    }
+
+    fn relieve(&self, _: &Vec<i64>, _: i64) -> Vec<i64> {
+        unreachable!("remainders can't be divided")
+    }
+
+    fn divisible(&self, level: &Vec<i64>, test: usize) -> bool {
+        level[test] == 0
+    }
+
+    /// Puts the remainders back together into the level modulo the least
+    /// common multiple of the tests, the same value `Lcm` keeps.
+    fn value(&self, level: &Vec<i64>) -> String {
+        let start = (BigInt::ZERO, BigInt::from(1));
+        let (value, _) = level
+            .iter()
+            .zip(&self.divisors)
+            .fold(start, |(a, m), (&b, &n)| {
+                // Solve a + m * k = b (mod n) for k, which only needs `a` and
+                // `m` modulo n. The modulus itself soon outgrows `i128`.
+                let n = n as i128;
+                let reduce = |x: &BigInt| i128::try_from(x % n).unwrap();
+                let (g, inverse, _) = extended_gcd(reduce(&m), n);
+                let step = n / g;
+                let k = ((b as i128 - reduce(&a)).rem_euclid(n) / g * inverse).rem_euclid(step);
+                let modulus = &m * step;
+                (a + m * k, modulus)
+            });
+        value.to_string()
+    }
+}
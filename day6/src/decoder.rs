@@ -0,0 +1,105 @@
+use std::io::{self, BufReader, Read};
+
+use crate::marker::{find_start, Window};
+
+#[derive(Debug, Clone)]
+pub struct MarkerSpec {
+    pub name: String,
+    pub window_size: usize,
+}
+
+impl MarkerSpec {
+    pub fn new(name: &str, window_size: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            window_size,
+        }
+    }
+}
+
+/// A packet or message: the marker that starts it and the data up to the next
+/// marker of the same kind (or the end of the stream).
+#[derive(Debug, PartialEq)]
+pub struct Frame<'a> {
+    pub kind: &'a str,
+    pub offset: usize,
+    pub payload: &'a [u8],
+}
+
+pub struct Decoded {
+    specs: Vec<MarkerSpec>,
+    offsets: Vec<Vec<usize>>,
+    data: Vec<u8>,
+}
+
+impl Decoded {
+    fn spec_index(&self, name: &str) -> Option<usize> {
+        self.specs.iter().position(|spec| spec.name == name)
+    }
+
+    /// Every position at which a marker of the given kind ends, `None` for
+    /// kinds that weren't decoded.
+    pub fn offsets(&self, name: &str) -> Option<&[usize]> {
+        self.spec_index(name).map(|index| &self.offsets[index][..])
+    }
+
+    pub fn first(&self, name: &str) -> Option<usize> {
+        self.offsets(name)?.first().copied()
+    }
+
+    /// Splits the stream into frames of the given kind. Markers overlapping the
+    /// previous one are part of its payload rather than a new frame, so each
+    /// frame starts at the first marker after the previous frame's start.
+    pub fn frames(&self, name: &str) -> Option<Vec<Frame<'_>>> {
+        let spec = &self.specs[self.spec_index(name)?];
+
+        let mut starts: Vec<usize> = vec![];
+        let mut from = 0;
+        while let Some(offset) = find_start(&self.data[from..], spec.window_size) {
+            starts.push(from + offset);
+            from += offset;
+        }
+
+        let frames = starts
+            .iter()
+            .enumerate()
+            .map(|(i, &offset)| {
+                let end = starts
+                    .get(i + 1)
+                    .map_or(self.data.len(), |next| next - spec.window_size);
+                Frame {
+                    kind: spec.name.as_str(),
+                    offset,
+                    payload: &self.data[offset..end],
+                }
+            })
+            .collect();
+        Some(frames)
+    }
+}
+
+/// Reads the whole stream once, feeding every byte to a window per spec.
+pub fn decode<R: Read>(reader: R, specs: &[MarkerSpec]) -> io::Result<Decoded> {
+    let mut windows = specs
+        .iter()
+        .map(|spec| Window::new(spec.window_size))
+        .collect::<Vec<_>>();
+    let mut offsets = vec![vec![]; specs.len()];
+    let mut data = vec![];
+
+    for byte in BufReader::new(reader).bytes() {
+        let byte = byte?;
+        data.push(byte);
+        for (window, offsets) in windows.iter_mut().zip(offsets.iter_mut()) {
+            if window.push(byte) {
+                offsets.push(window.position());
+            }
+        }
+    }
+
+    Ok(Decoded {
+        specs: specs.to_vec(),
+        offsets,
+        data,
+    })
+}
@@ -1,31 +1,49 @@
-use itertools::Itertools;
+mod decoder;
+mod marker;
 
-fn find_start(s: &str, window_size: usize) -> Option<usize> {
-    for (index, window) in s.chars().collect::<Vec<_>>().windows(window_size).enumerate() {
-        if window.iter().unique().count() == window_size {
-            return Some(index + window_size);
+use std::{env, io};
+
+use decoder::{decode, MarkerSpec};
+use marker::markers;
+
+fn solve(s: &str, specs: &[MarkerSpec]) {
+    let decoded = decode(s.as_bytes(), specs).expect("Reading from memory can't fail");
+    for spec in specs {
+        if let (Some(index), Some(frames)) = (decoded.first(&spec.name), decoded.frames(&spec.name))
+        {
+            println!("{}: {} ({} frames)", spec.name, index, frames.len());
+        } else {
+            println!("No {} marker found", spec.name);
         }
-    };
-    return None;
+    }
 }
 
-fn solve(s: &str, window_size: usize) -> () {
-    if let Some(index) = find_start(s, window_size) {
-        println!("{}: {:?}", s, index);
-    } else {
-        println!("No solution found for {}", s);
+/// Prints every marker of the given size found on stdin as soon as it arrives.
+fn stream(window_size: usize) {
+    for index in markers(io::stdin().lock(), window_size) {
+        println!("{}", index.expect("Failed to read stdin"));
     }
 }
 
 fn main() {
-    solve(include_str!("input"), 4);
-    solve(include_str!("input"), 14);
-}
+    if let Some(window_size) = env::args().nth(1) {
+        stream(window_size.parse().expect("Invalid window size"));
+        return;
+    }
 
+    solve(
+        include_str!("input"),
+        &[
+            MarkerSpec::new("start-of-packet", 4),
+            MarkerSpec::new("start-of-message", 14),
+        ],
+    );
+}
 
 #[cfg(test)]
 mod tests {
-    use crate::*;
+    use crate::{marker::find_start, *};
+    use std::io::Read;
 
     #[test]
     fn test_1() {
@@ -36,6 +54,48 @@ mod tests {
     #[test]
     fn test_2() {
         assert_eq!(find_start("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 14), Some(19));
-        assert_eq!(find_start("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 14), Some(29));
+        assert_eq!(
+            find_start("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 14),
+            Some(29)
+        );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn all_markers() {
+        let found = markers("abcabbcd".as_bytes(), 3)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(found, vec![3, 4, 5, 8]);
+    }
+
+    #[test]
+    fn endless_stream() {
+        let stream = std::io::repeat(b'a').take(1 << 20).chain("bcd".as_bytes());
+        assert_eq!(markers(stream, 4).next().unwrap().unwrap(), (1 << 20) + 3);
+    }
+
+    #[test]
+    fn decode_markers() {
+        let specs = [MarkerSpec::new("packet", 4), MarkerSpec::new("message", 14)];
+        let decoded = decode("mjqjpqmgbljsphdztnvjfqwrcgsmlb".as_bytes(), &specs).unwrap();
+        assert_eq!(decoded.first("packet"), Some(7));
+        assert_eq!(decoded.first("message"), Some(19));
+        assert_eq!(
+            decoded.offsets("message"),
+            Some(&[19, 25, 26, 27, 28, 29, 30][..])
+        );
+        assert_eq!(decoded.first("frame"), None);
+    }
+
+    #[test]
+    fn split_frames() {
+        let specs = [MarkerSpec::new("packet", 3)];
+        let decoded = decode("aaabcxxxdefyy".as_bytes(), &specs).unwrap();
+        let frames = decoded.frames("packet").unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].offset, 5);
+        assert_eq!(frames[0].payload, b"xx");
+        assert_eq!(frames[1].offset, 10);
+        assert_eq!(frames[1].payload, b"fyy");
+    }
+}
@@ -0,0 +1,123 @@
+use std::io::{self, ErrorKind, Read};
+
+const BUFFER_SIZE: usize = 4096;
+
+/// Sliding window over the last `size` bytes of a stream which keeps a
+/// frequency table, so checking whether all bytes differ is O(1) per byte.
+pub struct Window {
+    bytes: Vec<u8>,
+    counts: [usize; 256],
+    distinct: usize,
+    position: usize,
+}
+
+impl Window {
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "Window size must be positive");
+        Self {
+            bytes: vec![0; size],
+            counts: [0; 256],
+            distinct: 0,
+            position: 0,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Pushes the next byte and returns whether the window now ends on a marker.
+    pub fn push(&mut self, byte: u8) -> bool {
+        let slot = self.position % self.size();
+
+        if self.position >= self.size() {
+            let old = self.bytes[slot] as usize;
+            self.counts[old] -= 1;
+            if self.counts[old] == 0 {
+                self.distinct -= 1;
+            }
+        }
+
+        self.bytes[slot] = byte;
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 1 {
+            self.distinct += 1;
+        }
+        self.position += 1;
+
+        self.distinct == self.size()
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+/// Scans a byte stream for markers: positions where the last `window_size`
+/// bytes are all different. Every marker position is yielded (the number of
+/// bytes read so far), so the scanner can be left running on an endless stream.
+pub struct Markers<R: Read> {
+    reader: R,
+    buffer: Box<[u8; BUFFER_SIZE]>,
+    buffer_pos: usize,
+    buffer_len: usize,
+    window: Window,
+}
+
+impl<R: Read> Markers<R> {
+    pub fn new(reader: R, window_size: usize) -> Self {
+        Self {
+            reader,
+            buffer: Box::new([0; BUFFER_SIZE]),
+            buffer_pos: 0,
+            buffer_len: 0,
+            window: Window::new(window_size),
+        }
+    }
+
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        while self.buffer_pos == self.buffer_len {
+            match self.reader.read(&mut self.buffer[..]) {
+                Ok(0) => return Ok(None),
+                Ok(len) => {
+                    self.buffer_pos = 0;
+                    self.buffer_len = len;
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        let byte = self.buffer[self.buffer_pos];
+        self.buffer_pos += 1;
+        Ok(Some(byte))
+    }
+}
+
+impl<R: Read> Iterator for Markers<R> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_byte() {
+                Ok(Some(byte)) => {
+                    if self.window.push(byte) {
+                        return Some(Ok(self.window.position()));
+                    }
+                }
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+pub fn markers<R: Read>(reader: R, window_size: usize) -> Markers<R> {
+    Markers::new(reader, window_size)
+}
+
+/// Position of the first marker in data held in memory.
+pub fn find_start(data: impl AsRef<[u8]>, window_size: usize) -> Option<usize> {
+    markers(data.as_ref(), window_size)
+        .next()
+        .map(|index| index.expect("Reading from memory can't fail"))
+}
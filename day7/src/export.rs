@@ -0,0 +1,160 @@
+use std::{
+    fmt::Write as _,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use crate::fs::{FileSystem, NodeId};
+
+pub fn human_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["", "K", "M", "G", "T"];
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        size.to_string()
+    } else if value < 10.0 {
+        format!("{:.1}{}", value, UNITS[unit])
+    } else {
+        format!("{:.0}{}", value, UNITS[unit])
+    }
+}
+
+fn json_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn json_into(fs: &FileSystem, id: NodeId, out: &mut String) {
+    let file = fs.file(id);
+    out.push_str("{\"name\":");
+    json_string(&file.name, out);
+    write!(out, ",\"size\":{}", fs.get_size(id)).unwrap();
+    if file.is_dir() {
+        out.push_str(",\"children\":[");
+        for (i, &child) in file.content.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            json_into(fs, child, out);
+        }
+        out.push(']');
+    }
+    out.push('}');
+}
+
+/// Serialises the subtree under `id` as nested `{name, size, children}`
+/// objects, files have no `children`.
+pub fn to_json(fs: &FileSystem, id: NodeId) -> String {
+    let mut out = String::new();
+    json_into(fs, id, &mut out);
+    out
+}
+
+fn du_into(fs: &FileSystem, id: NodeId, human: bool, out: &mut String) {
+    for &child in &fs.file(id).content {
+        if fs.file(child).is_dir() {
+            du_into(fs, child, human, out);
+        }
+    }
+    let size = fs.get_size(id);
+    let size = if human {
+        human_size(size)
+    } else {
+        size.to_string()
+    };
+    writeln!(out, "{}\t{}", size, fs.path(id)).unwrap();
+}
+
+/// Lists every directory under `id` with its size, children first like `du`.
+pub fn to_du(fs: &FileSystem, id: NodeId, human: bool) -> String {
+    let mut out = String::new();
+    du_into(fs, id, human, &mut out);
+    out
+}
+
+fn check_name(name: &str) -> io::Result<()> {
+    if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Refusing to create a file named `{}`", name),
+        ));
+    }
+    Ok(())
+}
+
+/// Recreates the subtree under `id` inside `target`. Files are sparse, so they
+/// report the recorded size without taking up the space.
+pub fn write_tree(fs: &FileSystem, id: NodeId, target: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(target)?;
+    for &child in &fs.file(id).content {
+        let file = fs.file(child);
+        check_name(&file.name)?;
+        let path = target.join(&file.name);
+        match file.size {
+            Some(size) => std::fs::File::create(path)?.set_len(size)?,
+            None => write_tree(fs, child, &path)?,
+        }
+    }
+    Ok(())
+}
+
+fn transcript_into(dir: &Path, out: &mut String) -> io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    out.push_str("$ ls\n");
+    let mut dirs = vec![];
+    for entry in &entries {
+        // Following links could loop or leave the tree, so they're skipped.
+        if entry.file_type()?.is_symlink() {
+            continue;
+        }
+        let name = entry.file_name();
+        let name = match name.to_str() {
+            Some(name) if !name.contains(char::is_whitespace) => name.to_string(),
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Can't list `{}` in a transcript", entry.path().display()),
+                ))
+            }
+        };
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            writeln!(out, "dir {}", name).unwrap();
+            dirs.push((name, entry.path()));
+        } else {
+            writeln!(out, "{} {}", metadata.len(), name).unwrap();
+        }
+    }
+
+    for (name, path) in dirs {
+        writeln!(out, "$ cd {}", name).unwrap();
+        transcript_into(&path, out)?;
+        out.push_str("$ cd ..\n");
+    }
+    Ok(())
+}
+
+/// Produces a `$ cd` / `$ ls` transcript that `parse_fs` turns back into the
+/// directory at `root`. Symlinks are skipped, and names a transcript can't
+/// hold, with whitespace or not UTF-8, are an error.
+pub fn transcript_from_dir(root: &Path) -> io::Result<String> {
+    let mut out = "$ cd /\n".to_string();
+    transcript_into(root, &mut out)?;
+    Ok(out)
+}
@@ -0,0 +1,177 @@
+use std::fmt::Debug;
+
+pub type NodeId = usize;
+
+pub const ROOT: NodeId = 0;
+
+pub struct File {
+    pub name: String,
+    pub parent: Option<NodeId>,
+    pub content: Vec<NodeId>,
+    pub size: Option<u64>,
+    total_size: u64,
+}
+
+impl File {
+    pub fn is_dir(&self) -> bool {
+        self.size.is_none()
+    }
+}
+
+/// Filesystem tree stored in an arena. Nodes are only ever appended, so a
+/// child always has a higher id than its parent.
+pub struct FileSystem {
+    files: Vec<File>,
+}
+
+impl Default for FileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileSystem {
+    pub fn new() -> Self {
+        Self {
+            files: vec![File {
+                name: "/".to_string(),
+                parent: None,
+                content: vec![],
+                size: None,
+                total_size: 0,
+            }],
+        }
+    }
+
+    pub fn file(&self, id: NodeId) -> &File {
+        &self.files[id]
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = (NodeId, &File)> {
+        self.files.iter().enumerate()
+    }
+
+    pub fn dirs(&self) -> impl Iterator<Item = (NodeId, &File)> {
+        self.files().filter(|(_, f)| f.is_dir())
+    }
+
+    pub fn get_file(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        self.files[dir]
+            .content
+            .iter()
+            .copied()
+            .find(|&id| self.files[id].name == name)
+    }
+
+    /// Adds a file (with a size) or a directory (without one) to `dir`.
+    /// Sizes are stale until `update_sizes` is called.
+    pub fn add(&mut self, dir: NodeId, name: &str, size: Option<u64>) -> NodeId {
+        let id = self.files.len();
+        self.files.push(File {
+            name: name.to_string(),
+            parent: Some(dir),
+            content: vec![],
+            size,
+            total_size: size.unwrap_or(0),
+        });
+        self.files[dir].content.push(id);
+        id
+    }
+
+    /// Recomputes every directory size in a single bottom-up pass. The files
+    /// must add up to at most `u64::MAX`, which `parse_fs` checks.
+    pub fn update_sizes(&mut self) {
+        for id in (0..self.files.len()).rev() {
+            if self.files[id].is_dir() {
+                self.files[id].total_size = self.files[id]
+                    .content
+                    .iter()
+                    .map(|&child| self.files[child].total_size)
+                    .sum();
+            }
+        }
+    }
+
+    pub fn get_size(&self, id: NodeId) -> u64 {
+        self.files[id].total_size
+    }
+
+    pub fn path(&self, id: NodeId) -> String {
+        let mut names = vec![];
+        let mut current = Some(id);
+        while let Some(id) = current {
+            if id != ROOT {
+                names.push(self.files[id].name.as_str());
+            }
+            current = self.files[id].parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    /// Follows a `/`-separated path, absolute or relative to `from`.
+    pub fn resolve(&self, from: NodeId, path: &str) -> Option<NodeId> {
+        let mut current = if path.starts_with('/') { ROOT } else { from };
+        for part in path.split('/').filter(|p| !p.is_empty()) {
+            current = match part {
+                "." => current,
+                ".." => self.files[current].parent.unwrap_or(ROOT),
+                name => self.get_file(current, name)?,
+            };
+        }
+        Some(current)
+    }
+
+    /// Sum of the sizes of all directories that are at most `bound` big.
+    pub fn get_bound_size(&self, bound: u64) -> u64 {
+        self.dirs()
+            .map(|(id, _)| self.get_size(id))
+            .filter(|&size| size <= bound)
+            .sum()
+    }
+
+    /// Size of the smallest directory that frees up enough space once deleted,
+    /// leaving at least `needed_space` of the disk unused.
+    pub fn find_smallest_file(&self, disk_size: u64, needed_space: u64) -> Option<u64> {
+        let to_free = needed_space
+            .checked_add(self.get_size(ROOT))?
+            .saturating_sub(disk_size);
+        self.dirs()
+            .map(|(id, _)| self.get_size(id))
+            .filter(|&size| size >= to_free)
+            .min()
+    }
+}
+
+impl Debug for FileSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn entry(fs: &FileSystem, id: NodeId) -> DebugEntry<'_> {
+            DebugEntry { fs, id }
+        }
+
+        struct DebugEntry<'a> {
+            fs: &'a FileSystem,
+            id: NodeId,
+        }
+
+        impl Debug for DebugEntry<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                let file = self.fs.file(self.id);
+                f.debug_struct("File")
+                    .field(
+                        "content",
+                        &file
+                            .content
+                            .iter()
+                            .map(|&id| entry(self.fs, id))
+                            .collect::<Vec<_>>(),
+                    )
+                    .field("name", &file.name)
+                    .field("size", &file.size)
+                    .finish()
+            }
+        }
+
+        entry(self, ROOT).fmt(f)
+    }
+}
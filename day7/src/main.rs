@@ -1,167 +1,238 @@
-use std::{
-    borrow::{Borrow, BorrowMut},
-    cell::RefCell,
-    fmt::Debug,
-    ops::Deref,
-    rc::Rc,
-};
-
-struct File {
-    content: Vec<Rc<RefCell<File>>>,
-    parent: Option<Rc<RefCell<File>>>,
-    name: String,
-    size: Option<u64>,
-}
+mod export;
+mod fs;
+mod parser;
+mod planner;
+mod shell;
+
+use std::{env, path::Path, process};
+
+use export::{to_du, to_json, transcript_from_dir, write_tree};
+use fs::ROOT;
+use parser::parse_fs;
+use planner::{plan_deletions, DiskModel};
+use shell::Shell;
 
 const MAX_MEMORY: u64 = 70000000;
-const NEEDED_SPACE : u64 = 30000000;
-
-impl Debug for File {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("File")
-            .field("content", &self.content)
-            .field("name", &self.name)
-            .field("size", &self.size)
-            .finish()
-    }
-}
+const NEEDED_SPACE: u64 = 30000000;
+const SIZE_BOUND: u64 = 100000;
+const ALTERNATIVES: usize = 3;
 
-impl File {
-    fn get_file(&self, name: &str) -> Rc<RefCell<File>> {
-        let file = self
-            .content
-            .iter()
-            .find(|f| f.deref().deref().borrow().name == name)
-            .expect("Couldn't find file");
-        file.clone()
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    if let ["import", dir] = args[1..] {
+        print!(
+            "{}",
+            transcript_from_dir(Path::new(dir)).expect("Failed to read directory")
+        );
+        return;
     }
 
-    fn get_size(&self) -> u64 {
-        if let Some(size) = self.size {
-            size
-        } else {
-            self.content
-                .iter()
-                .map(|f| f.deref().borrow().get_size())
-                .sum()
+    let fs = match parse_fs(include_str!("input")) {
+        Ok(fs) => fs,
+        Err(e) => {
+            eprintln!("Invalid transcript: {}", e);
+            process::exit(1);
         }
-    }
-
-    fn get_bound_size(&self) -> u64 {
-        let size = if self.get_size() > 100000 || self.size.is_some() {
-            0
-        } else {
-            self.get_size()
-        };
-        size + self
-            .content
-            .iter()
-            .map(|f| f.deref().borrow().get_bound_size())
-            .sum::<u64>()
-    }
+    };
 
-    fn find_smallest_file(&self, unused_space: u64, min: &mut u64) -> u64 {
-        if self.size.is_some() {
-            return *min;
+    match args[1..] {
+        ["shell"] => {
+            Shell::new(&fs).run().expect("Failed to run shell");
+            return;
         }
-        let size = self.get_size();
-        if size + unused_space > NEEDED_SPACE {
-            *min = if size < *min {
-                size
-            } else {
-                *min
-            }
+        ["export", "json"] => {
+            println!("{}", to_json(&fs, ROOT));
+            return;
         }
-        for file in self.content.iter() {
-            file.deref().borrow().find_smallest_file(unused_space, min);
+        ["export", "du"] => {
+            print!("{}", to_du(&fs, ROOT, false));
+            return;
         }
-        *min
+        ["export", "dir", ..] => {
+            let target = match args.get(3) {
+                Some(dir) => Path::new(dir).to_path_buf(),
+                None => env::temp_dir().join("day7-fs"),
+            };
+            write_tree(&fs, ROOT, &target).expect("Failed to write directory tree");
+            println!("Wrote filesystem to {}", target.display());
+            return;
+        }
+        _ => {}
     }
 
+    println!("Total size: {:?}", fs.get_bound_size(SIZE_BOUND));
+
+    println!(
+        "Smallest size: {}",
+        fs.find_smallest_file(MAX_MEMORY, NEEDED_SPACE)
+            .expect("No directory is big enough")
+    );
+
+    let model = DiskModel {
+        disk_size: MAX_MEMORY,
+        required_space: NEEDED_SPACE,
+    };
+    for (rank, plan) in plan_deletions(&fs, model, ALTERNATIVES).iter().enumerate() {
+        let dirs = plan.dirs.iter().map(|&id| fs.path(id)).collect::<Vec<_>>();
+        println!(
+            "Plan {}: delete {:?}, freeing {} ({} free)",
+            rank + 1,
+            dirs,
+            plan.freed,
+            plan.free_space
+        );
+    }
 }
 
-fn parse_fs(s: &str) -> Rc<RefCell<File>> {
-    let mut root: Rc<RefCell<File>> = Rc::new(RefCell::new(File {
-        name: "/".to_string(),
-        content: vec![],
-        parent: None,
-        size: None,
-    }));
-    let mut current = Rc::clone(&root);
-
-    for ele in s.lines().map(|s| s.split(" ").collect::<Vec<_>>()).skip(1) {
-        if ele.get(0).unwrap() == &"$" {
-            if ele.get(1).unwrap() == &"cd" {
-                match ele.get(2).unwrap() {
-                    &".." => {
-                        let parent = current.deref().borrow().parent.clone().unwrap();
-                        current = parent;
-                    }
-                    &"/" => {
-                        current = root.clone();
-                    }
-                    s => {
-                        let child = current.deref().borrow().get_file(s);
-                        current = child;
-                    }
-                }
-            }
-        } else {
-            if ele.get(0).unwrap() == &"dir" {
-                let name = ele.get(1).unwrap();
-
-                let file = Rc::new(RefCell::new(File {
-                    name: name.to_string(),
-                    content: vec![],
-                    parent: Some(current.clone()),
-                    size: None,
-                }));
-
-                current
-                    .borrow_mut()
-                    .deref()
-                    .deref()
-                    .borrow_mut()
-                    .content
-                    .push(file.clone());
-            } else {
-                let size = ele
-                    .get(0)
-                    .unwrap()
-                    .parse::<_>()
-                    .expect("Failed to parse file size");
-                let name = ele.get(1).unwrap().to_string();
-
-                let current = current.borrow_mut();
-
-                let mut file = Rc::new(RefCell::new(File {
-                    content: vec![],
-                    name,
-                    parent: Some(current.clone()),
-                    size: Some(size),
-                }));
-
-                current
-                    .deref()
-                    .deref()
-                    .borrow_mut()
-                    .content
-                    .push(file.clone());
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use parser::{ParseError, ParseErrorKind};
+
+    #[test]
+    fn example() {
+        let fs = parse_fs(include_str!("example")).unwrap();
+        assert_eq!(fs.get_bound_size(SIZE_BOUND), 95437);
+        assert_eq!(
+            fs.find_smallest_file(MAX_MEMORY, NEEDED_SPACE),
+            Some(24933642)
+        );
+        // Even deleting everything leaves only 1000 free.
+        assert_eq!(fs.find_smallest_file(1000, NEEDED_SPACE), None);
+        let overfull =
+            parse_fs("$ ls\ndir a\ndir b\n$ cd a\n$ ls\n100 x\n$ cd /\n$ cd b\n$ ls\n50 y\n")
+                .unwrap();
+        assert_eq!(overfull.find_smallest_file(100, 30), Some(100));
     }
 
-    root
-}
+    #[test]
+    fn deletion_plans() {
+        let fs = parse_fs("$ ls\ndir a\ndir b\ndir c\n$ cd a\n$ ls\n50 x\n$ cd ..\n$ cd b\n$ ls\n30 y\n$ cd ..\n$ cd c\n$ ls\n25 z\n").unwrap();
+        let model = DiskModel {
+            disk_size: 120,
+            required_space: 55,
+        };
+        let plans = plan_deletions(&fs, model, 2);
+        assert_eq!(plans[0].freed, 50);
+        assert_eq!(plans[0].free_space, 65);
+        assert_eq!(plans[1].freed, 55);
+        assert_eq!(plans[1].dirs.len(), 2);
+
+        // Gigabyte sizes, `a` only holds `h` so deleting either is one plan,
+        // and deleting both `a` and `b` frees more than needed.
+        let fs = parse_fs(
+            "$ ls\ndir a\ndir b\n10000000000 r\n$ cd a\n$ ls\ndir h\n$ cd h\n\
+             $ ls\n40000000000 x\n$ cd /\n$ cd b\n$ ls\n30000000000 y\n",
+        )
+        .unwrap();
+        let model = DiskModel {
+            disk_size: 100000000000,
+            required_space: 50000000000,
+        };
+        let plans = plan_deletions(&fs, model, 3);
+        let dirs = plans
+            .iter()
+            .map(|plan| plan.dirs.iter().map(|&id| fs.path(id)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(dirs, [["/b"], ["/a"]]);
+        assert_eq!(plans[1].free_space, 60000000000);
+
+        // 150 used on a disk of 100, so 80 has to go and only `a` is enough.
+        let fs = parse_fs("$ ls\ndir a\ndir b\n$ cd a\n$ ls\n100 x\n$ cd /\n$ cd b\n$ ls\n50 y\n")
+            .unwrap();
+        let model = DiskModel {
+            disk_size: 100,
+            required_space: 30,
+        };
+        let plans = plan_deletions(&fs, model, 2);
+        assert_eq!(plans.len(), 1);
+        assert_eq!(fs.path(plans[0].dirs[0]), "/a");
+        assert_eq!(plans[0].free_space, 50);
+    }
 
-fn main() {
-    let root = parse_fs(include_str!("input"));
-    println!(
-        "Total size: {:?}",
-        root.deref().deref().borrow().get_bound_size()
-    );
+    #[test]
+    fn export_round_trip() {
+        let fs = parse_fs(include_str!("example")).unwrap();
+        let target = env::temp_dir().join(format!("day7-test-{}", process::id()));
+        write_tree(&fs, ROOT, &target).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(target.join("a"), target.join("a/link")).unwrap();
+        let copy = parse_fs(&transcript_from_dir(&target).unwrap()).unwrap();
+        std::fs::File::create(target.join("d/two words")).unwrap();
+        let spaced = transcript_from_dir(&target);
+        std::fs::remove_dir_all(&target).unwrap();
+
+        assert_eq!(
+            spaced.map_err(|e| e.kind()),
+            Err(std::io::ErrorKind::InvalidData)
+        );
+        assert_eq!(to_du(&copy, ROOT, false), to_du(&fs, ROOT, false));
+        assert_eq!(
+            to_json(&copy, copy.resolve(ROOT, "a/e").unwrap()),
+            r#"{"name":"e","size":584,"children":[{"name":"i","size":584}]}"#
+        );
+    }
+
+    #[test]
+    fn shell() {
+        let fs = parse_fs(include_str!("example")).unwrap();
+        let mut shell = Shell::new(&fs);
+        shell.execute("cd a/e").unwrap();
+        assert_eq!(shell.execute("pwd").unwrap(), "/a/e\n");
+        assert_eq!(shell.execute("ls").unwrap(), "584 i\n");
+        assert_eq!(
+            shell.execute("find / -size +9M -type d").unwrap(),
+            "/\n/d\n"
+        );
+        assert!(shell.execute("cd nope").is_err());
+        assert_eq!(
+            shell.execute("find / -size +99999999999G"),
+            Err("size too large: 99999999999G".to_string())
+        );
+    }
 
-    let mut size = root.deref().deref().borrow().get_size();
-    let unused_space = MAX_MEMORY - size;
-    println!("Smallest size: {}", root.deref().deref().borrow().find_smallest_file(unused_space, &mut size));
+    #[test]
+    fn repeated_listings() {
+        let fs = parse_fs("$ cd /\n$ ls\n1 a\ndir b\n$ ls\n1 a\n$ cd c\n$ ls\n2 d\n").unwrap();
+        assert_eq!(fs.file(fs::ROOT).content.len(), 3);
+        assert_eq!(fs.get_size(fs::ROOT), 3);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse_fs("$ ls\n1 a\n$ ls\n2 a").err(),
+            Some(ParseError {
+                line: 4,
+                kind: ParseErrorKind::ConflictingEntry {
+                    name: "a".to_string(),
+                    old: Some(1),
+                    new: Some(2)
+                }
+            })
+        );
+        assert_eq!(
+            parse_fs("$ cd /\n$ rm -rf a").err(),
+            Some(ParseError {
+                line: 2,
+                kind: ParseErrorKind::UnknownCommand("rm".to_string())
+            })
+        );
+        assert_eq!(
+            parse_fs("$ ls\n18446744073709551615 a\n1 b").err(),
+            Some(ParseError {
+                line: 3,
+                kind: ParseErrorKind::SizeOverflow
+            })
+        );
+        assert_eq!(
+            parse_fs("$ cd /\n$ ls -la").err(),
+            Some(ParseError {
+                line: 2,
+                kind: ParseErrorKind::UnexpectedArgument("ls", "-la".to_string())
+            })
+        );
+    }
 }
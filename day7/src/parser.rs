@@ -0,0 +1,148 @@
+use std::fmt::Display;
+
+use crate::fs::{FileSystem, NodeId, ROOT};
+
+#[derive(Debug, PartialEq)]
+pub enum ParseErrorKind {
+    UnknownCommand(String),
+    MissingArgument(&'static str),
+    UnexpectedArgument(&'static str, String),
+    InvalidSize(String),
+    SizeOverflow,
+    MalformedEntry(String),
+    OutputWithoutLs,
+    NotADirectory(String),
+    ConflictingEntry {
+        name: String,
+        old: Option<u64>,
+        new: Option<u64>,
+    },
+}
+
+/// Error in a transcript, `line` is 1-based.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let describe = |size: &Option<u64>| match size {
+            Some(size) => format!("a file of size {}", size),
+            None => "a directory".to_string(),
+        };
+        match self {
+            Self::UnknownCommand(command) => write!(f, "unknown command `{}`", command),
+            Self::MissingArgument(command) => write!(f, "`{}` is missing an argument", command),
+            Self::UnexpectedArgument(command, argument) => {
+                write!(f, "unexpected argument `{}` to `{}`", argument, command)
+            }
+            Self::InvalidSize(size) => write!(f, "invalid file size `{}`", size),
+            Self::SizeOverflow => write!(f, "the files add up to more than {} bytes", u64::MAX),
+            Self::MalformedEntry(entry) => write!(f, "malformed listing entry `{}`", entry),
+            Self::OutputWithoutLs => write!(f, "listing output without a preceding `ls`"),
+            Self::NotADirectory(name) => write!(f, "`{}` is not a directory", name),
+            Self::ConflictingEntry { name, old, new } => write!(
+                f,
+                "`{}` was listed as {} and now as {}",
+                name,
+                describe(old),
+                describe(new)
+            ),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Adds an entry from an `ls` listing, ignoring it if the same entry was
+/// already listed before.
+fn add_entry(
+    fs: &mut FileSystem,
+    dir: NodeId,
+    name: &str,
+    size: Option<u64>,
+) -> Result<NodeId, ParseErrorKind> {
+    match fs.get_file(dir, name) {
+        Some(id) if fs.file(id).size == size => Ok(id),
+        Some(id) => Err(ParseErrorKind::ConflictingEntry {
+            name: name.to_string(),
+            old: fs.file(id).size,
+            new: size,
+        }),
+        None => Ok(fs.add(dir, name, size)),
+    }
+}
+
+fn cd(fs: &mut FileSystem, current: NodeId, target: &str) -> Result<NodeId, ParseErrorKind> {
+    match target {
+        "/" => Ok(ROOT),
+        ".." => Ok(fs.file(current).parent.unwrap_or(ROOT)),
+        name => match fs.get_file(current, name) {
+            Some(id) if fs.file(id).is_dir() => Ok(id),
+            Some(_) => Err(ParseErrorKind::NotADirectory(name.to_string())),
+            None => Ok(fs.add(current, name, None)),
+        },
+    }
+}
+
+/// Rebuilds a filesystem from a `$ cd` / `$ ls` terminal transcript.
+pub fn parse_fs(s: &str) -> Result<FileSystem, ParseError> {
+    let mut fs = FileSystem::new();
+    let mut current = ROOT;
+    let mut listing = false;
+    // Every directory size is a part of this, so they can't overflow either.
+    let mut total_size = 0u64;
+
+    for (index, line) in s.lines().enumerate() {
+        let error = |kind| ParseError {
+            line: index + 1,
+            kind,
+        };
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+
+        match parts.as_slice() {
+            [] => {}
+            ["$", "cd", target] => {
+                listing = false;
+                current = cd(&mut fs, current, target).map_err(error)?;
+            }
+            ["$", "cd"] => return Err(error(ParseErrorKind::MissingArgument("cd"))),
+            ["$", "ls"] => listing = true,
+            ["$", "ls", argument, ..] | ["$", "cd", _, argument, ..] => {
+                let command = if parts[1] == "ls" { "ls" } else { "cd" };
+                let argument = argument.to_string();
+                return Err(error(ParseErrorKind::UnexpectedArgument(command, argument)));
+            }
+            ["$", command, ..] => {
+                return Err(error(ParseErrorKind::UnknownCommand(command.to_string())))
+            }
+            _ if !listing => return Err(error(ParseErrorKind::OutputWithoutLs)),
+            ["dir", name] => {
+                add_entry(&mut fs, current, name, None).map_err(error)?;
+            }
+            [size, name] => {
+                let size = size
+                    .parse()
+                    .map_err(|_| error(ParseErrorKind::InvalidSize(size.to_string())))?;
+                if fs.get_file(current, name).is_none() {
+                    total_size = total_size
+                        .checked_add(size)
+                        .ok_or_else(|| error(ParseErrorKind::SizeOverflow))?;
+                }
+                add_entry(&mut fs, current, name, Some(size)).map_err(error)?;
+            }
+            _ => return Err(error(ParseErrorKind::MalformedEntry(line.to_string()))),
+        }
+    }
+
+    fs.update_sizes();
+    Ok(fs)
+}
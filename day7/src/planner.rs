@@ -0,0 +1,168 @@
+use std::collections::BTreeSet;
+
+use crate::fs::{FileSystem, NodeId, ROOT};
+
+#[derive(Debug, Clone, Copy)]
+pub struct DiskModel {
+    pub disk_size: u64,
+    pub required_space: u64,
+}
+
+/// A set of directories to delete, none of which contains another.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub dirs: Vec<NodeId>,
+    pub freed: u64,
+    pub free_space: u64,
+}
+
+/// Branch and bound over sets of directories. `frontier` holds the
+/// directories still to decide on, none inside another or a chosen one, so
+/// their total is the most that can still be freed. Each is either deleted
+/// whole or left, in which case its subdirectories join the frontier.
+struct Search<'a> {
+    fs: &'a FileSystem,
+    needed: u64,
+    alternatives: usize,
+    frontier: Vec<NodeId>,
+    chosen: Vec<NodeId>,
+    best: Vec<(u64, Vec<NodeId>)>,
+}
+
+impl Search<'_> {
+    fn subdirs(&self, dir: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.fs
+            .file(dir)
+            .content
+            .iter()
+            .copied()
+            .filter(|&id| self.fs.file(id).is_dir())
+    }
+
+    /// The total no plan needs to reach anymore, once enough were found.
+    fn bound(&self) -> Option<u64> {
+        (self.best.len() == self.alternatives).then(|| self.best.last().unwrap().0)
+    }
+
+    fn done(&self) -> bool {
+        self.bound() == Some(self.needed)
+    }
+
+    /// Records the chosen directories unless one of them could be left out.
+    /// Directories holding nothing but subdirectories which are all chosen
+    /// are recorded as the directory itself, so no two plans delete the same
+    /// files.
+    fn record(&mut self, freed: u64) {
+        let smallest = self.chosen.iter().map(|&id| self.fs.get_size(id)).min();
+        if smallest.is_some_and(|size| freed - size >= self.needed) {
+            return;
+        }
+
+        let mut dirs = self.chosen.iter().copied().collect::<BTreeSet<_>>();
+        // Children have higher ids, so the deepest directories come last.
+        while let Some(parent) = dirs
+            .iter()
+            .rev()
+            .filter_map(|&id| self.fs.file(id).parent)
+            .find(|&parent| {
+                parent != ROOT
+                    && self
+                        .fs
+                        .file(parent)
+                        .content
+                        .iter()
+                        .all(|id| dirs.contains(id))
+            })
+        {
+            for &child in &self.fs.file(parent).content {
+                dirs.remove(&child);
+            }
+            dirs.insert(parent);
+        }
+        let dirs = dirs.into_iter().collect::<Vec<_>>();
+        if self.best.iter().any(|(_, other)| *other == dirs) {
+            return;
+        }
+
+        let index = self.best.partition_point(|(total, _)| *total <= freed);
+        self.best.insert(index, (freed, dirs));
+        self.best.truncate(self.alternatives);
+    }
+
+    fn visit(&mut self, freed: u64, available: u64) {
+        if self.bound().is_some_and(|bound| freed >= bound) || freed + available < self.needed {
+            return;
+        }
+        if freed >= self.needed {
+            // Deleting anything more would only make the plan worse.
+            self.record(freed);
+            return;
+        }
+        let Some(dir) = self.frontier.pop() else {
+            return;
+        };
+        let size = self.fs.get_size(dir);
+
+        self.chosen.push(dir);
+        self.visit(freed + size, available - size);
+        self.chosen.pop();
+
+        if !self.done() {
+            let start = self.frontier.len();
+            let mut subdirs = self.subdirs(dir).collect::<Vec<_>>();
+            subdirs.sort_unstable_by_key(|&id| self.fs.get_size(id));
+            let inside = subdirs.iter().map(|&id| self.fs.get_size(id)).sum::<u64>();
+            self.frontier.extend(subdirs);
+            self.visit(freed, available - size + inside);
+            self.frontier.truncate(start);
+        }
+
+        self.frontier.push(dir);
+    }
+}
+
+/// Finds the distinct sets of directories with the smallest total size whose
+/// deletion leaves at least `required_space` free, best first. Directories
+/// are never nested and none can be dropped from a plan, as plans stop
+/// growing once they free enough. The root isn't deleted.
+pub fn plan_deletions(fs: &FileSystem, model: DiskModel, alternatives: usize) -> Vec<Plan> {
+    let used = fs.get_size(ROOT);
+    let free_space = |freed| model.disk_size.saturating_sub(used - freed);
+    // An overfull disk needs its excess freed on top of the required space.
+    let Some(needed) = model.required_space.checked_add(used) else {
+        return vec![];
+    };
+    let needed = needed.saturating_sub(model.disk_size);
+    if needed == 0 {
+        return vec![Plan {
+            dirs: vec![],
+            freed: 0,
+            free_space: free_space(0),
+        }];
+    }
+
+    let mut search = Search {
+        fs,
+        needed,
+        alternatives: alternatives.max(1),
+        frontier: vec![],
+        chosen: vec![],
+        best: vec![],
+    };
+    let mut top = search.subdirs(ROOT).collect::<Vec<_>>();
+    top.sort_unstable_by_key(|&id| fs.get_size(id));
+    let available = top.iter().map(|&id| fs.get_size(id)).sum();
+    search.frontier = top;
+    search.visit(0, available);
+
+    search
+        .best
+        .into_iter()
+        .take(alternatives)
+        .map(|(freed, dirs)| Plan {
+            dirs,
+            freed,
+            free_space: free_space(freed),
+        })
+        .collect()
+}
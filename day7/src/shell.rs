@@ -0,0 +1,213 @@
+use std::{
+    fmt::Write as _,
+    io::{self, BufRead, Write},
+};
+
+use crate::{
+    export::{human_size, to_du},
+    fs::{FileSystem, NodeId, ROOT},
+};
+
+/// A tiny read-only shell over a reconstructed filesystem.
+pub struct Shell<'a> {
+    fs: &'a FileSystem,
+    cwd: NodeId,
+}
+
+fn parse_size(s: &str) -> Result<u64, String> {
+    let (digits, multiplier) = match s.chars().last() {
+        Some('k' | 'K') => (&s[..s.len() - 1], 1 << 10),
+        Some('M') => (&s[..s.len() - 1], 1 << 20),
+        Some('G') => (&s[..s.len() - 1], 1 << 30),
+        _ => (s, 1),
+    };
+    let n = digits
+        .parse::<u64>()
+        .map_err(|_| format!("invalid size: {}", s))?;
+    n.checked_mul(multiplier)
+        .ok_or_else(|| format!("size too large: {}", s))
+}
+
+impl<'a> Shell<'a> {
+    pub fn new(fs: &'a FileSystem) -> Self {
+        Self { fs, cwd: ROOT }
+    }
+
+    fn resolve(&self, path: Option<&str>) -> Result<NodeId, String> {
+        match path {
+            None => Ok(self.cwd),
+            Some(path) => self
+                .fs
+                .resolve(self.cwd, path)
+                .ok_or_else(|| format!("{}: No such file or directory", path)),
+        }
+    }
+
+    /// Runs a single command line and returns what it printed.
+    pub fn execute(&mut self, line: &str) -> Result<String, String> {
+        let args = line.split_whitespace().collect::<Vec<_>>();
+        let Some((&command, args)) = args.split_first() else {
+            return Ok(String::new());
+        };
+
+        match command {
+            "cd" => self.cd(args.first().copied().unwrap_or("/")),
+            "ls" => self.ls(self.resolve(args.first().copied())?),
+            "pwd" => Ok(format!("{}\n", self.fs.path(self.cwd))),
+            "du" => {
+                let human = args.first() == Some(&"-h");
+                let path = args.iter().copied().find(|a| !a.starts_with('-'));
+                Ok(to_du(self.fs, self.resolve(path)?, human))
+            }
+            "find" => self.find(args),
+            "tree" => self.tree(args),
+            _ => Err(format!("{}: command not found", command)),
+        }
+    }
+
+    fn cd(&mut self, path: &str) -> Result<String, String> {
+        let id = self.resolve(Some(path))?;
+        if !self.fs.file(id).is_dir() {
+            return Err(format!("cd: {}: Not a directory", path));
+        }
+        self.cwd = id;
+        Ok(String::new())
+    }
+
+    fn ls(&self, id: NodeId) -> Result<String, String> {
+        let file = self.fs.file(id);
+        if !file.is_dir() {
+            return Ok(format!("{} {}\n", self.fs.get_size(id), file.name));
+        }
+        let mut out = String::new();
+        for &child in &file.content {
+            let child = self.fs.file(child);
+            match child.size {
+                Some(size) => writeln!(out, "{} {}", size, child.name).unwrap(),
+                None => writeln!(out, "dir {}", child.name).unwrap(),
+            }
+        }
+        Ok(out)
+    }
+
+    /// `find [path] [-size [+-]N[kMG]] [-type f|d]`
+    fn find(&self, args: &[&str]) -> Result<String, String> {
+        let mut start = self.cwd;
+        let mut filters: Vec<Box<dyn Fn(NodeId) -> bool + '_>> = vec![];
+        let mut args = args.iter();
+
+        while let Some(&arg) = args.next() {
+            match arg {
+                "-size" => {
+                    let spec = args.next().ok_or("find: missing argument to -size")?;
+                    let (cmp, size) = if let Some(size) = spec.strip_prefix('+') {
+                        ('+', parse_size(size)?)
+                    } else if let Some(size) = spec.strip_prefix('-') {
+                        ('-', parse_size(size)?)
+                    } else {
+                        ('=', parse_size(spec)?)
+                    };
+                    filters.push(Box::new(move |id| {
+                        let actual = self.fs.get_size(id);
+                        match cmp {
+                            '+' => actual > size,
+                            '-' => actual < size,
+                            _ => actual == size,
+                        }
+                    }));
+                }
+                "-type" => {
+                    let dirs = match args.next().copied() {
+                        Some("d") => true,
+                        Some("f") => false,
+                        _ => return Err("find: -type expects f or d".to_string()),
+                    };
+                    filters.push(Box::new(move |id| self.fs.file(id).is_dir() == dirs));
+                }
+                path => start = self.resolve(Some(path))?,
+            }
+        }
+
+        let mut out = String::new();
+        let mut stack = vec![start];
+        while let Some(id) = stack.pop() {
+            if filters.iter().all(|filter| filter(id)) {
+                writeln!(out, "{}", self.fs.path(id)).unwrap();
+            }
+            stack.extend(self.fs.file(id).content.iter().rev());
+        }
+        Ok(out)
+    }
+
+    /// `tree [-L depth] [path]`
+    fn tree(&self, args: &[&str]) -> Result<String, String> {
+        let mut depth = usize::MAX;
+        let mut path = None;
+        let mut args = args.iter();
+
+        while let Some(&arg) = args.next() {
+            if arg == "-L" {
+                depth = args
+                    .next()
+                    .and_then(|d| d.parse().ok())
+                    .ok_or("tree: -L expects a number")?;
+            } else {
+                path = Some(arg);
+            }
+        }
+
+        let id = self.resolve(path)?;
+        let mut out = format!("{}\n", path.unwrap_or("."));
+        self.tree_into(id, "", depth, &mut out);
+        Ok(out)
+    }
+
+    fn tree_into(&self, id: NodeId, prefix: &str, depth: usize, out: &mut String) {
+        if depth == 0 {
+            return;
+        }
+        let content = &self.fs.file(id).content;
+        for (i, &child) in content.iter().enumerate() {
+            let last = i == content.len() - 1;
+            let file = self.fs.file(child);
+            let branch = if last { "└── " } else { "├── " };
+            writeln!(
+                out,
+                "{}{}{} ({})",
+                prefix,
+                branch,
+                file.name,
+                human_size(self.fs.get_size(child))
+            )
+            .unwrap();
+            let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+            self.tree_into(child, &prefix, depth - 1, out);
+        }
+    }
+
+    /// Reads commands from stdin until `exit` or end of input.
+    pub fn run(&mut self) -> io::Result<()> {
+        let stdin = io::stdin();
+        let mut stdout = io::stdout();
+        let mut lines = stdin.lock().lines();
+
+        loop {
+            write!(stdout, "{} $ ", self.fs.path(self.cwd))?;
+            stdout.flush()?;
+
+            let Some(line) = lines.next() else {
+                writeln!(stdout)?;
+                return Ok(());
+            };
+            let line = line?;
+            if line.trim() == "exit" {
+                return Ok(());
+            }
+
+            match self.execute(&line) {
+                Ok(out) => write!(stdout, "{}", out)?,
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+    }
+}
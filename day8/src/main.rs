@@ -1,98 +1,332 @@
-type Grid = Vec<Vec<u8>>;
-
-fn create_grid(s: &str) -> Grid {
-    s.lines()
-        .map(|s| {
-            s.chars()
-                .map(|c| c.to_digit(10).expect("Incorrect number for a tree") as u8)
-                .collect::<Vec<_>>()
-        })
-        .collect()
+mod maps;
+mod sweep;
+
+use std::{env, fs};
+
+use maps::{Layer, TreeMaps};
+use sweep::{analyse, Direction, Rules, View};
+
+type Height = u16;
+type Grid = Vec<Vec<Height>>;
+
+#[derive(Debug, Clone, Copy)]
+enum Encoding {
+    /// One digit per tree, the puzzle's format.
+    Digits,
+    /// One character per tree, `0-9` then `a-z` then `A-Z` for heights up to 61.
+    Alphanumeric,
+    /// Whitespace or comma separated numbers.
+    Separated,
 }
 
-fn find_visible_trees(grid: &Grid) -> usize {
-    let height = grid.len();
-    let width = grid[0].len();
-    let mut visible = width * 2 + height * 2 - 4;
-    for y in 1..height - 1 {
-        let row = &grid[y];
-        for x in 1..width - 1 {
-            let column = grid.iter().map(|r| r[x]).collect::<Vec<_>>();
-            let tree = row[x];
-            let (top, mut bottom) = column.split_at(y);
-            bottom = &bottom[1..];
-            let (left, mut right) = row.split_at(x);
-            right = &right[1..];
-
-            let directions = [left, right, top, bottom]
-                .iter()
-                .map(|&dir| dir.iter().find(|&&i| i >= tree))
-                .map(|o| o.is_some())
-                .collect::<Vec<_>>();
-            if directions.contains(&false) {
-                visible += 1;
-            }
+impl Encoding {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "digits" => Some(Self::Digits),
+            "alnum" => Some(Self::Alphanumeric),
+            "separated" => Some(Self::Separated),
+            _ => None,
         }
     }
+}
 
-    visible
+fn alphanumeric_height(c: char) -> Height {
+    match c {
+        '0'..='9' => c as Height - '0' as Height,
+        'a'..='z' => c as Height - 'a' as Height + 10,
+        'A'..='Z' => c as Height - 'A' as Height + 36,
+        _ => panic!("Incorrect height for a tree: {}", c),
+    }
 }
 
-fn calc_scenic_score((x, y): (usize, usize), grid: &Grid) -> u32 {
-    let row = &grid[y];
-    let column = grid.iter().map(|r| r[x]).collect::<Vec<_>>();
+fn create_grid(s: &str, encoding: Encoding) -> Grid {
+    let grid = s
+        .lines()
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| match encoding {
+            Encoding::Digits => s
+                .chars()
+                .map(|c| c.to_digit(10).expect("Incorrect number for a tree") as Height)
+                .collect::<Vec<_>>(),
+            Encoding::Alphanumeric => s.chars().map(alphanumeric_height).collect(),
+            Encoding::Separated => s
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse().expect("Incorrect number for a tree"))
+                .collect(),
+        })
+        .collect::<Grid>();
+    assert!(
+        grid.iter().all(|row| row.len() == grid[0].len()),
+        "Forest isn't rectangular"
+    );
+    grid
+}
 
-    let (top, mut bottom) = column.split_at(y);
-    bottom = &bottom[1..];
-    let (left, mut right) = row.split_at(x);
-    right = &right[1..];
+/// Walks from a tree towards the edge, the direct counterpart of `sweep`.
+fn look((x, y): (usize, usize), grid: &Grid, direction: Direction, rules: &Rules) -> View {
+    let tree = grid[y][x];
+    let (dx, dy) = direction.step();
+    let mut distance = 0;
+    let (mut x, mut y) = (x as isize, y as isize);
 
-    let tree = row[x];
+    loop {
+        x += dx;
+        y += dy;
+        let Some(&other) = grid
+            .get(y as usize)
+            .and_then(|row: &Vec<Height>| row.get(x as usize))
+        else {
+            return (distance, true);
+        };
+        if rules.max_distance == Some(distance) {
+            return (distance, false);
+        }
+        distance += 1;
+        if rules.blocks(other, tree) {
+            return (distance, false);
+        }
+    }
+}
 
-    let sides: [&mut dyn Iterator<Item = &u8>; 4] = [
-        &mut top.iter().rev(),
-        &mut left.iter().rev(),
-        &mut bottom.iter(),
-        &mut right.iter(),
-    ];
+fn is_visible(position: (usize, usize), grid: &Grid, rules: &Rules) -> bool {
+    rules
+        .directions()
+        .iter()
+        .any(|&direction| look(position, grid, direction, rules).1)
+}
 
-    let mut scores = vec![];
+fn calc_scenic_score(position: (usize, usize), grid: &Grid, rules: &Rules) -> u64 {
+    rules
+        .directions()
+        .iter()
+        .map(|&direction| look(position, grid, direction, rules).0 as u64)
+        .product()
+}
 
-    for side in sides {
-        let mut score = 0;
-        for i in side {
-            score += 1;
-            if *i >= tree {
-                break;
+fn main() {
+    let mut rules = Rules::default();
+    let mut encoding = Encoding::Digits;
+    let mut input = None;
+
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let mut args = args.iter().map(String::as_str).peekable();
+    while let Some(&flag) = args.peek() {
+        if !flag.starts_with("--") {
+            break;
+        }
+        args.next();
+        match flag {
+            "--diagonals" => rules.diagonals = true,
+            "--max-distance" => {
+                rules.max_distance = Some(args.next().unwrap().parse().expect("Invalid distance"))
+            }
+            "--transparency" => {
+                rules.transparency = args.next().unwrap().parse().expect("Invalid height")
             }
+            "--encoding" => {
+                encoding = Encoding::parse(args.next().unwrap()).expect("Unknown encoding")
+            }
+            "--input" => input = Some(fs::read_to_string(args.next().unwrap()).unwrap()),
+            _ => panic!("Unknown option {}", flag),
         }
-        scores.push(score);
     }
 
-    scores.iter().fold(1, |acc, x| acc * x)
+    let grid = create_grid(input.as_deref().unwrap_or(include_str!("input")), encoding);
+
+    // Diagonal distances are only computed when the rules look along them.
+    let parse_layer = |name| match Layer::parse(name) {
+        Some(Layer::Distance(direction)) if !rules.directions().contains(&direction) => {
+            panic!("The {} layer needs --diagonals", name)
+        }
+        layer => layer.expect("Unknown layer"),
+    };
+
+    match args.collect::<Vec<_>>()[..] {
+        ["map", layer] => {
+            let layer = parse_layer(layer);
+            print!("{}", TreeMaps::new(&grid, &rules).text_heatmap(layer));
+        }
+        ["image", layer, path] => {
+            let layer = parse_layer(layer);
+            let maps = TreeMaps::new(&grid, &rules);
+            let image = if path.ends_with(".ppm") {
+                maps.to_ppm(layer)
+            } else {
+                maps.to_pgm(layer)
+            };
+            fs::write(path, image).expect("Failed to write image");
+        }
+        ["tree", x, y] => {
+            let position = (x.parse().expect("Invalid x"), y.parse().expect("Invalid y"));
+            let maps = TreeMaps::new(&grid, &rules);
+            for &direction in rules.directions() {
+                println!("{:?}: {}", direction, maps.distance(position, direction));
+            }
+            println!("Visible from: {:?}", maps.visible_from(position));
+            println!("Visible: {}", is_visible(position, &grid, &rules));
+            println!(
+                "Scenic score: {}",
+                calc_scenic_score(position, &grid, &rules)
+            );
+        }
+        _ => {
+            let summary = analyse(&grid, &rules);
+            println!("Visible trees: {}", summary.visible);
+            println!(
+                "Highest scenic score: {} at {:?}",
+                summary.best_score, summary.best_position
+            );
+        }
+    }
 }
 
-fn find_highest_scenic_score(grid: &Grid) -> u32 {
-    let height = grid.len();
-    let width = grid[0].len();
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    // The original quadratic-per-tree implementations, kept as a reference.
 
-    let mut max = 0;
+    fn reference_visible_trees(grid: &Grid) -> usize {
+        let height = grid.len();
+        let width = grid[0].len();
+        let mut visible = width * 2 + height * 2 - 4;
+        for y in 1..height - 1 {
+            let row = &grid[y];
+            for x in 1..width - 1 {
+                let column = grid.iter().map(|r| r[x]).collect::<Vec<_>>();
+                let tree = row[x];
+                let (top, mut bottom) = column.split_at(y);
+                bottom = &bottom[1..];
+                let (left, mut right) = row.split_at(x);
+                right = &right[1..];
 
-    for y in 1..height - 1 {
-        for x in 1..width - 1 {
-            let score = calc_scenic_score((x, y), grid);
-            if score > max {
-                max = score;
+                let directions = [left, right, top, bottom]
+                    .iter()
+                    .map(|&dir| dir.iter().find(|&&i| i >= tree))
+                    .map(|o| o.is_some())
+                    .collect::<Vec<_>>();
+                if directions.contains(&false) {
+                    visible += 1;
+                }
             }
         }
+
+        visible
     }
 
-    max
-}
+    fn reference_scenic_score((x, y): (usize, usize), grid: &Grid) -> u32 {
+        let row = &grid[y];
+        let column = grid.iter().map(|r| r[x]).collect::<Vec<_>>();
 
-fn main() {
-    let grid = create_grid(include_str!("input"));
-    println!("Visible trees: {}", find_visible_trees(&grid));
+        let (top, mut bottom) = column.split_at(y);
+        bottom = &bottom[1..];
+        let (left, mut right) = row.split_at(x);
+        right = &right[1..];
+
+        let tree = row[x];
+
+        let sides: [&mut dyn Iterator<Item = &Height>; 4] = [
+            &mut top.iter().rev(),
+            &mut left.iter().rev(),
+            &mut bottom.iter(),
+            &mut right.iter(),
+        ];
+
+        let mut scores = vec![];
+
+        for side in sides {
+            let mut score = 0;
+            for i in side {
+                score += 1;
+                if *i >= tree {
+                    break;
+                }
+            }
+            scores.push(score);
+        }
 
-    println!("Highest scenic score: {}", find_highest_scenic_score(&grid));
+        scores.iter().product()
+    }
+
+    #[test]
+    fn tree_maps() {
+        let grid = create_grid(include_str!("example"), Encoding::Digits);
+        let maps = TreeMaps::new(&grid, &Rules::default());
+        assert_eq!(maps.layer(Layer::Score)[3 * 5 + 2], 8);
+        assert_eq!(maps.distance((2, 3), Direction::Down), 1);
+        assert_eq!(
+            maps.visible_from((1, 1)),
+            vec![Direction::Left, Direction::Up]
+        );
+        assert_eq!(
+            maps.layer(Layer::Visibility)
+                .iter()
+                .filter(|&&v| v > 0)
+                .count(),
+            21
+        );
+        assert!(maps.to_pgm(Layer::Score).starts_with(b"P5\n5 5\n255\n"));
+    }
+
+    #[test]
+    fn matches_reference() {
+        let rules = Rules::default();
+        for grid in [
+            create_grid(include_str!("example"), Encoding::Digits),
+            create_grid(include_str!("input"), Encoding::Digits),
+        ] {
+            let summary = analyse(&grid, &rules);
+            assert_eq!(summary.visible, reference_visible_trees(&grid));
+
+            let (height, width) = (grid.len(), grid[0].len());
+            for y in 0..height {
+                for x in 0..width {
+                    let score = reference_scenic_score((x, y), &grid);
+                    assert!(score as u64 <= summary.best_score);
+                }
+            }
+            let (x, y) = summary.best_position;
+            assert_eq!(
+                reference_scenic_score((x, y), &grid) as u64,
+                summary.best_score
+            );
+        }
+    }
+
+    #[test]
+    fn rules_match_walking() {
+        let grid = create_grid(include_str!("input"), Encoding::Digits);
+        let wide = create_grid("0 12 5 40\n7,3,30,2\n100 1 1 99\n", Encoding::Separated);
+        assert_eq!(
+            create_grid("9aZ\n", Encoding::Alphanumeric),
+            vec![vec![9, 10, 61]]
+        );
+
+        for diagonals in [false, true] {
+            for max_distance in [None, Some(0), Some(3)] {
+                for transparency in [0, 1, 3] {
+                    let rules = Rules {
+                        diagonals,
+                        max_distance,
+                        transparency,
+                    };
+                    for grid in [&grid, &wide] {
+                        let scores = TreeMaps::new(grid, &rules).layer(Layer::Score);
+                        let visible = (0..grid.len())
+                            .flat_map(|y| (0..grid[0].len()).map(move |x| (x, y)))
+                            .filter(|&position| {
+                                assert_eq!(
+                                    scores[position.1 * grid[0].len() + position.0],
+                                    calc_scenic_score(position, grid, &rules)
+                                );
+                                is_visible(position, grid, &rules)
+                            })
+                            .count();
+                        assert_eq!(analyse(grid, &rules).visible, visible);
+                    }
+                }
+            }
+        }
+    }
 }
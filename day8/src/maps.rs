@@ -0,0 +1,151 @@
+use crate::{
+    sweep::{scenic_score, sweep, Direction, Rules, View},
+    Grid,
+};
+
+const GLYPHS: &[u8] = b" .:-=+*#%@";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Height,
+    Score,
+    Distance(Direction),
+    /// Number of directions a tree can be seen from.
+    Visibility,
+}
+
+impl Layer {
+    pub fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "height" => Self::Height,
+            "score" => Self::Score,
+            "visibility" => Self::Visibility,
+            "left" => Self::Distance(Direction::Left),
+            "right" => Self::Distance(Direction::Right),
+            "up" => Self::Distance(Direction::Up),
+            "down" => Self::Distance(Direction::Down),
+            "up-left" => Self::Distance(Direction::UpLeft),
+            "up-right" => Self::Distance(Direction::UpRight),
+            "down-left" => Self::Distance(Direction::DownLeft),
+            "down-right" => Self::Distance(Direction::DownRight),
+            _ => return None,
+        })
+    }
+}
+
+/// Views of every tree in a forest, in row-major order.
+pub struct TreeMaps {
+    pub width: usize,
+    pub height: usize,
+    directions: &'static [Direction],
+    heights: Vec<u64>,
+    views: Vec<View>,
+}
+
+impl TreeMaps {
+    pub fn new(grid: &Grid, rules: &Rules) -> Self {
+        let directions = rules.directions();
+        let mut views = Vec::with_capacity(grid.len() * grid[0].len() * directions.len());
+        sweep(grid, rules, |_, _, tree| views.extend_from_slice(tree));
+        Self {
+            width: grid[0].len(),
+            height: grid.len(),
+            directions,
+            heights: grid.iter().flatten().map(|&h| h as u64).collect(),
+            views,
+        }
+    }
+
+    fn tree_views(&self, index: usize) -> &[View] {
+        let count = self.directions.len();
+        &self.views[index * count..(index + 1) * count]
+    }
+
+    fn views(&self, (x, y): (usize, usize)) -> &[View] {
+        self.tree_views(y * self.width + x)
+    }
+
+    fn direction_index(&self, direction: Direction) -> usize {
+        self.directions
+            .iter()
+            .position(|&d| d == direction)
+            .expect("Direction isn't part of the rules")
+    }
+
+    pub fn visible_from(&self, position: (usize, usize)) -> Vec<Direction> {
+        self.directions
+            .iter()
+            .zip(self.views(position))
+            .filter(|(_, &(_, visible))| visible)
+            .map(|(&direction, _)| direction)
+            .collect()
+    }
+
+    pub fn distance(&self, position: (usize, usize), direction: Direction) -> u32 {
+        self.views(position)[self.direction_index(direction)].0
+    }
+
+    /// Values of a layer for every tree, in row-major order.
+    pub fn layer(&self, layer: Layer) -> Vec<u64> {
+        self.heights
+            .iter()
+            .enumerate()
+            .map(|(i, &height)| (self.tree_views(i), height))
+            .map(|(views, height)| match layer {
+                Layer::Height => height,
+                Layer::Score => scenic_score(views),
+                Layer::Distance(direction) => views[self.direction_index(direction)].0 as u64,
+                Layer::Visibility => views.iter().filter(|&&(_, v)| v).count() as u64,
+            })
+            .collect()
+    }
+
+    /// Scales a layer to `0.0..=1.0`. Scores grow multiplicatively, so they are
+    /// scaled logarithmically to keep the smaller ones distinguishable.
+    fn normalised(&self, layer: Layer) -> Vec<f64> {
+        let values = self.layer(layer);
+        let scale = |v: u64| match layer {
+            Layer::Score => (v as f64).ln_1p(),
+            _ => v as f64,
+        };
+        let max = values.iter().copied().map(scale).fold(0.0, f64::max);
+        values
+            .into_iter()
+            .map(|v| if max > 0.0 { scale(v) / max } else { 0.0 })
+            .collect()
+    }
+
+    pub fn text_heatmap(&self, layer: Layer) -> String {
+        let mut out = String::new();
+        for row in self.normalised(layer).chunks(self.width) {
+            for &v in row {
+                let glyph = GLYPHS[(v * (GLYPHS.len() - 1) as f64).round() as usize];
+                out.push(glyph as char);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Binary greyscale PGM (P5) image of a layer, brighter is higher.
+    pub fn to_pgm(&self, layer: Layer) -> Vec<u8> {
+        let mut out = format!("P5\n{} {}\n255\n", self.width, self.height).into_bytes();
+        out.extend(
+            self.normalised(layer)
+                .into_iter()
+                .map(|v| (v * 255.0).round() as u8),
+        );
+        out
+    }
+
+    /// Binary colour PPM (P6) image of a layer, going from blue to red.
+    pub fn to_ppm(&self, layer: Layer) -> Vec<u8> {
+        let mut out = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for v in self.normalised(layer) {
+            let red = (v * 255.0).round() as u8;
+            let green = ((1.0 - (2.0 * v - 1.0).abs()) * 255.0).round() as u8;
+            out.extend([red, green, 255 - red]);
+        }
+        out
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{Grid, Height};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+/// Order in which views are passed to `sweep` callbacks. The cardinal
+/// directions come first, so four-directional rules use a prefix of it.
+pub const DIRECTIONS: [Direction; 8] = [
+    Direction::Left,
+    Direction::Right,
+    Direction::Up,
+    Direction::Down,
+    Direction::UpLeft,
+    Direction::UpRight,
+    Direction::DownLeft,
+    Direction::DownRight,
+];
+
+impl Direction {
+    /// Offset of the next tree when looking in this direction.
+    pub fn step(self) -> (isize, isize) {
+        match self {
+            Self::Left => (-1, 0),
+            Self::Right => (1, 0),
+            Self::Up => (0, -1),
+            Self::Down => (0, 1),
+            Self::UpLeft => (-1, -1),
+            Self::UpRight => (1, -1),
+            Self::DownLeft => (-1, 1),
+            Self::DownRight => (1, 1),
+        }
+    }
+
+    fn looks_down(self) -> bool {
+        matches!(self, Self::Down | Self::DownLeft | Self::DownRight)
+    }
+
+    /// The line of sight through `(x, y)` in this direction, and how many
+    /// trees lie between `(x, y)` and the edge it is looking towards.
+    fn line(self, (x, y): (usize, usize), width: usize, height: usize) -> (usize, usize) {
+        let (left, right, up, down) = (x, width - 1 - x, y, height - 1 - y);
+        match self {
+            Self::Left => (y, left),
+            Self::Right => (y, right),
+            Self::Up => (x, up),
+            Self::Down => (x, down),
+            Self::UpLeft => (x + down, left.min(up)),
+            Self::UpRight => (x + y, right.min(up)),
+            Self::DownLeft => (x + y, left.min(down)),
+            Self::DownRight => (x + down, right.min(down)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Rules {
+    /// Also look along the four diagonals.
+    pub diagonals: bool,
+    /// How many trees far one can see, views stop there.
+    pub max_distance: Option<u32>,
+    /// Trees shorter than the viewer by `transparency` or more don't block
+    /// its view. The puzzle's rule is 1, where any tree at least as tall
+    /// blocks, and 0 lets only taller trees block.
+    pub transparency: Height,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            diagonals: false,
+            max_distance: None,
+            transparency: 1,
+        }
+    }
+}
+
+impl Rules {
+    pub fn directions(&self) -> &'static [Direction] {
+        if self.diagonals {
+            &DIRECTIONS
+        } else {
+            &DIRECTIONS[..4]
+        }
+    }
+
+    pub fn blocks(&self, tree: Height, viewer: Height) -> bool {
+        tree as u32 + self.transparency as u32 > viewer as u32
+    }
+
+    /// Caps a view at the maximum distance. Trees further away than that can't
+    /// be seen from the edge either.
+    fn limit(&self, (distance, visible): View) -> View {
+        match self.max_distance {
+            Some(max) if distance > max => (max, false),
+            _ => (distance, visible),
+        }
+    }
+}
+
+/// Trees behind the current one that can still block a view, kept with
+/// strictly decreasing heights. A tree hides every tree behind it that isn't
+/// taller, so each tree is pushed and popped at most once per line.
+#[derive(Default, Clone)]
+pub struct Blockers(Vec<(Height, usize)>);
+
+impl Blockers {
+    /// Looks back from the tree at `position` along the line and returns its
+    /// view towards the line's start.
+    pub fn look(&mut self, position: usize, height: Height, rules: &Rules) -> View {
+        let blocking = self.0.partition_point(|&(h, _)| rules.blocks(h, height));
+        let view = match blocking.checked_sub(1).map(|i| self.0[i]) {
+            Some((_, blocker)) => ((position - blocker) as u32, false),
+            None => (position as u32, true),
+        };
+
+        while matches!(self.0.last(), Some(&(h, _)) if h <= height) {
+            self.0.pop();
+        }
+        self.0.push((height, position));
+
+        rules.limit(view)
+    }
+}
+
+/// Viewing distance and whether the tree is visible from the edge.
+pub type View = (u32, bool);
+
+/// Blockers for every line of sight in one direction.
+struct Lines {
+    direction: Direction,
+    lines: Vec<Blockers>,
+}
+
+impl Lines {
+    fn new(direction: Direction, width: usize, height: usize) -> Self {
+        Self {
+            direction,
+            lines: vec![Blockers::default(); width + height],
+        }
+    }
+
+    fn look(&mut self, grid: &Grid, (x, y): (usize, usize), rules: &Rules) -> View {
+        let (line, position) = self.direction.line((x, y), grid[0].len(), grid.len());
+        self.lines[line].look(position, grid[y][x], rules)
+    }
+}
+
+/// Computes the views of every tree with one sweep per direction and passes
+/// them to `visit` row by row, ordered like `rules.directions()`. Views
+/// looking down and right are computed ahead, the others on the fly.
+pub fn sweep(grid: &Grid, rules: &Rules, mut visit: impl FnMut(usize, usize, &[View])) {
+    let height = grid.len();
+    let width = grid[0].len();
+    let directions = rules.directions();
+
+    let mut below = directions
+        .iter()
+        .filter(|d| d.looks_down())
+        .map(|&d| Lines::new(d, width, height))
+        .collect::<Vec<_>>();
+    let stride = below.len();
+    let mut stored = vec![(0, false); width * height * stride];
+    for y in (0..height).rev() {
+        for x in 0..width {
+            for (i, lines) in below.iter_mut().enumerate() {
+                stored[(y * width + x) * stride + i] = lines.look(grid, (x, y), rules);
+            }
+        }
+    }
+
+    let mut right = vec![(0, false); width];
+    let mut views = [(0, false); DIRECTIONS.len()];
+    let mut lines = directions
+        .iter()
+        .map(|&d| Lines::new(d, width, height))
+        .collect::<Vec<_>>();
+    for y in 0..height {
+        let mut row = Blockers::default();
+        for x in (0..width).rev() {
+            right[x] = row.look(width - 1 - x, grid[y][x], rules);
+        }
+
+        for x in 0..width {
+            let mut stored = stored[(y * width + x) * stride..].iter();
+            for (view, lines) in views.iter_mut().zip(lines.iter_mut()) {
+                *view = match lines.direction {
+                    Direction::Right => right[x],
+                    d if d.looks_down() => *stored.next().unwrap(),
+                    _ => lines.look(grid, (x, y), rules),
+                };
+            }
+            visit(x, y, &views[..directions.len()]);
+        }
+    }
+}
+
+pub fn scenic_score(views: &[View]) -> u64 {
+    views.iter().map(|&(d, _)| d as u64).product()
+}
+
+pub struct Summary {
+    pub visible: usize,
+    pub best_score: u64,
+    pub best_position: (usize, usize),
+}
+
+pub fn analyse(grid: &Grid, rules: &Rules) -> Summary {
+    let mut summary = Summary {
+        visible: 0,
+        best_score: 0,
+        best_position: (0, 0),
+    };
+
+    sweep(grid, rules, |x, y, views| {
+        if views.iter().any(|&(_, visible)| visible) {
+            summary.visible += 1;
+        }
+        let score = scenic_score(views);
+        if score > summary.best_score {
+            summary.best_score = score;
+            summary.best_position = (x, y);
+        }
+    });
+
+    summary
+}
@@ -0,0 +1,114 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Write},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    render::{visualize, Bounds},
+    rope::Rope,
+    Point,
+};
+
+/// Knot positions after every step of a rope's run, the first frame being
+/// the starting position.
+pub struct Recording {
+    frames: Vec<Vec<Point>>,
+}
+
+impl Recording {
+    pub fn from_rope(rope: &Rope) -> Self {
+        let knots = (0..rope.knot_count())
+            .map(|k| rope.history(k))
+            .collect::<Vec<_>>();
+        let frames = (0..knots[0].len())
+            .map(|step| knots.iter().map(|h| h[step].clone()).collect())
+            .collect();
+        Self { frames }
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        Bounds::around(self.frames.iter().flatten())
+    }
+
+    /// Plays every `every`th frame in the terminal at `fps` frames per second.
+    pub fn replay(
+        &self,
+        out: &mut impl Write,
+        fps: f64,
+        every: usize,
+        viewport: Option<Bounds>,
+    ) -> io::Result<()> {
+        assert!(every > 0, "Frame interval must be positive");
+        let delay = Duration::from_secs_f64(1.0 / fps);
+        for (step, knots) in self.frames.iter().enumerate().step_by(every) {
+            // Clear the screen and move the cursor home before every frame.
+            write!(out, "\x1b[2J\x1b[H")?;
+            writeln!(out, "Step {}/{}", step, self.frames.len() - 1)?;
+            write!(out, "{}", visualize(knots, viewport))?;
+            out.flush()?;
+            thread::sleep(delay);
+        }
+        Ok(())
+    }
+
+    /// Writes every `every`th frame as a binary PPM image into `dir`, with
+    /// `scale` pixels per cell. The tail's trail is drawn in grey and the
+    /// knots fade from red at the head to blue at the tail.
+    pub fn write_ppm_frames(
+        &self,
+        dir: &Path,
+        scale: usize,
+        every: usize,
+        viewport: Option<Bounds>,
+    ) -> io::Result<usize> {
+        assert!(every > 0, "Frame interval must be positive");
+        fs::create_dir_all(dir)?;
+        let bounds = viewport.unwrap_or_else(|| self.bounds());
+        let (width, height) = (bounds.width(), bounds.height());
+        let index =
+            |p: &Point| (bounds.max_y - p.y) as usize * width + (p.x - bounds.min_x) as usize;
+
+        let mut trail = HashSet::new();
+        let mut written = 0;
+        for (step, knots) in self.frames.iter().enumerate() {
+            trail.insert(knots.last().unwrap().clone());
+            if step % every != 0 {
+                continue;
+            }
+
+            let mut cells = vec![[255u8; 3]; width * height];
+            for p in trail.iter().filter(|p| bounds.contains(p)) {
+                cells[index(p)] = [200, 200, 200];
+            }
+            let start = Point::new(0, 0);
+            if bounds.contains(&start) {
+                cells[index(&start)] = [0, 0, 0];
+            }
+            let last = (knots.len() - 1).max(1);
+            for (i, knot) in knots.iter().enumerate().rev() {
+                if bounds.contains(knot) {
+                    let t = (i * 255 / last) as u8;
+                    cells[index(knot)] = [255 - t, 0, t];
+                }
+            }
+
+            let mut image = format!("P6\n{} {}\n255\n", width * scale, height * scale).into_bytes();
+            for row in cells.chunks(width) {
+                for _ in 0..scale {
+                    for cell in row {
+                        for _ in 0..scale {
+                            image.extend_from_slice(cell);
+                        }
+                    }
+                }
+            }
+            fs::write(dir.join(format!("frame{:06}.ppm", step)), image)?;
+            written += 1;
+        }
+        Ok(written)
+    }
+}
@@ -1,9 +1,18 @@
-use std::collections::HashSet;
+mod animation;
+mod physics;
+mod render;
+mod rope;
+mod trail;
 
-use lending_iterator::{lending_iterator::constructors::windows_mut, LendingIterator};
+use std::{env, fs, io, path::Path, time::Duration};
 
-const WIDTH: usize = 40;
-const HEIGHT: usize = 40;
+use animation::Recording;
+use physics::{FollowRule, COMPARED_RULES};
+use render::{path_svg, visualize, visualize_path, Bounds};
+use rope::Rope;
+
+const KNOTS: usize = 10;
+const FRAME_SCALE: usize = 4;
 
 #[derive(Debug, PartialEq)]
 enum Direction {
@@ -15,23 +24,23 @@ enum Direction {
 
 struct Instruction {
     dir: Direction,
-    step: u8,
+    step: u32,
 }
 
-#[derive(Hash, PartialEq, Eq, Clone)]
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
 struct Point {
-    x: i16,
-    y: i16,
+    x: i64,
+    y: i64,
 }
 
-impl From<(i16, i16)> for Point {
-    fn from((x, y): (i16, i16)) -> Self {
+impl From<(i64, i64)> for Point {
+    fn from((x, y): (i64, i64)) -> Self {
         Self { x, y }
     }
 }
 
 impl Point {
-    fn new(x: i16, y: i16) -> Self {
+    fn new(x: i64, y: i64) -> Self {
         Self { x, y }
     }
 }
@@ -55,111 +64,134 @@ impl From<&str> for Instruction {
     }
 }
 
-fn visualize(segments: &Vec<Point>) {
-    let mut board = vec![vec![".".to_string(); WIDTH]; HEIGHT];
-
-    let mid = WIDTH as i16 / 2;
+fn main() {
+    let mut input = None;
+    let mut knots = KNOTS;
+    let mut show_steps = false;
+    let mut show_path = false;
+    let mut svg = None;
+    let mut viewport = None;
+    let mut replay = None;
+    let mut frames = None;
+    let mut every = 1;
+    let mut rule = FollowRule::Diagonal;
+    let mut compare = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--steps" => show_steps = true,
+            "--path" => show_path = true,
+            "--svg" => svg = Some(args.next().expect("Missing SVG path")),
+            "--replay" => {
+                let fps = args.next().expect("Missing frame rate");
+                let fps = fps
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|fps| Duration::try_from_secs_f64(1.0 / fps).is_ok())
+                    .expect("Frame rate must be a positive number");
+                replay = Some(fps);
+            }
+            "--frames" => frames = Some(args.next().expect("Missing frame directory")),
+            "--every" => {
+                let n = args.next().expect("Missing frame interval");
+                every = n
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .expect("Frame interval must be a positive number");
+            }
+            "--viewport" => {
+                let bounds = args.next().expect("Missing viewport");
+                viewport = Some(Bounds::parse(&bounds).expect("Invalid viewport"));
+            }
+            "--rule" => {
+                let name = args.next().expect("Missing follow rule");
+                rule = FollowRule::parse(&name).expect("Invalid follow rule");
+            }
+            "--compare" => compare = true,
+            "--input" => {
+                let path = args.next().expect("Missing input path");
+                input = Some(fs::read_to_string(path).expect("Failed to read input"));
+            }
+            n => {
+                knots = n
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .expect("Knot count must be a positive number")
+            }
+        }
+    }
 
-    board[mid as usize][mid as usize] = "s".to_string();
+    let instructions = input
+        .as_deref()
+        .unwrap_or(include_str!("input"))
+        .lines()
+        .map(Instruction::from)
+        .collect::<Vec<_>>();
 
-    for (i, segment) in segments.iter().enumerate() {
-        board[(mid + segment.y) as usize][(mid + segment.x) as usize] = i.to_string();
+    if compare {
+        compare_rules(&instructions, knots);
+        return;
     }
 
-    board.iter().rev().for_each(|row| {
-        let row = row.iter().fold(String::new(), |acc, x| acc + x);
-        println!("{}", row);
-    })
-}
-
-fn visualize_path(path: &HashSet<Point>) {
-    let mut board = vec![vec![".".to_string(); WIDTH]; HEIGHT];
+    let mut rope = Rope::new(knots, rule);
 
-    let mid = WIDTH as i16 / 2;
+    for instruction in &instructions {
+        rope.apply(instruction);
+        if show_steps {
+            println!("{:?} {}", instruction.dir, instruction.step);
+            println!("{}", visualize(rope.knots(), viewport));
+        }
+    }
 
-    board[mid as usize][mid as usize] = "s".to_string();
+    let tail = knots - 1;
+    if show_path {
+        println!("{}", visualize_path(&rope.visited_points(tail), viewport));
+    }
+    if let Some(path) = svg {
+        fs::write(path, path_svg(rope.trail(tail))).expect("Failed to write SVG");
+    }
 
-    for point in path {
-        board[(mid + point.y) as usize][(mid + point.x) as usize] = "#".to_string();
+    // A recording holds every step, so it's only made when it's shown.
+    if replay.is_some() || frames.is_some() {
+        let recording = Recording::from_rope(&rope);
+        if let Some(fps) = replay {
+            recording
+                .replay(&mut io::stdout(), fps, every, viewport)
+                .expect("Failed to replay");
+        }
+        if let Some(dir) = frames {
+            let written = recording
+                .write_ppm_frames(Path::new(&dir), FRAME_SCALE, every, viewport)
+                .expect("Failed to write frames");
+            println!("Wrote {} frames to {}", written, dir);
+        }
     }
 
-    board.iter().rev().for_each(|row| {
-        let row = row.iter().fold(String::new(), |acc, x| acc + x);
-        println!("{}", row);
-    })
+    for knot in 1..knots {
+        println!("Visited points (knot {}): {}", knot, rope.visited(knot));
+    }
 }
 
-fn main() {
-    let instructions = include_str!("input")
-        .lines()
-        .map(Instruction::from)
-        .collect::<Vec<_>>();
-
-    // let mut head = Point::new(0, 0);
-    // let mut tail = Point::new(0, 0);
-
-    let mut segments = vec![Point::new(0, 0); 10];
-
-    let mut visited_points = HashSet::<Point>::new();
-
-    visited_points.insert(segments.last().unwrap().clone());
-
-    for Instruction { dir, step } in instructions {
-        for _ in 0..step {
-            move_in_dir(&dir, segments.first_mut().unwrap());
-
-            segments.windows_mut::<2>().for_each(|window| {
-                let (head, child) = window.split_first_mut().unwrap();
-
-                let mut child = &mut child[0];
-
-                let diff_x = head.x - child.x;
-                let diff_y = head.y - child.y;
-
-                if diff_x.abs() <= 1 && diff_y.abs() <= 1 {
-                    return;
-                }
-
-                let x_dir = if head.x == child.x {
-                    0
-                } else if head.x > child.x {
-                    1
-                } else {
-                    -1
-                };
-                let y_dir = if head.y == child.y {
-                    0
-                } else if head.y > child.y {
-                    1
-                } else {
-                    -1
-                };
-
-                if child.x == head.x || child.y == head.y {
-                    let dir = match (x_dir, y_dir) {
-                        (1, _) => Direction::Right,
-                        (-1, _) => Direction::Left,
-                        (_, 1) => Direction::Up,
-                        (_, -1) => Direction::Down,
-                        _ => unreachable!(),
-                    };
-
-                    move_in_dir(&dir, &mut child)
-                } else {
-                    child.x += x_dir;
-                    child.y += y_dir;
-                }
-            });
-
-            visited_points.insert(segments.last().unwrap().clone());
+/// Prints how many cells every knot visits under each of the compared rules.
+fn compare_rules(instructions: &[Instruction], knots: usize) {
+    print!("{:<12}", "rule");
+    for knot in 1..knots {
+        print!("{:>8}", format!("knot {}", knot));
+    }
+    println!();
+
+    for rule in COMPARED_RULES {
+        let mut rope = Rope::new(knots, rule);
+        instructions.iter().for_each(|i| rope.apply(i));
+        print!("{:<12}", rule.to_string());
+        for knot in 1..knots {
+            print!("{:>8}", rope.visited(knot));
         }
-        // println!("{:?} {}", dir, step);
-        // visualize(&segments);
+        println!();
     }
-
-    // visualize_path(&visited_points);
-
-    println!("Visited points: {}", visited_points.len());
 }
 
 fn move_in_dir(dir: &Direction, point: &mut Point) {
@@ -170,3 +202,130 @@ fn move_in_dir(dir: &Direction, point: &mut Point) {
         Direction::Right => point.x += 1,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn example() {
+        let mut rope = Rope::new(10, FollowRule::Diagonal);
+        include_str!("example")
+            .lines()
+            .map(Instruction::from)
+            .for_each(|instruction| rope.apply(&instruction));
+        assert_eq!(rope.visited(9), 36);
+        assert_eq!(rope.history(0).len(), 97);
+        assert_eq!(
+            visualize_path(&rope.visited_points(9), None),
+            "#.....................\n#.............###.....\n#............#...#....\n.#..........#.....#...\n..#..........#.....#..\n...#........#.......#.\n....#......s.........#\n.....#..............#.\n......#............#..\n.......#..........#...\n........#........#....\n.........########.....\n"
+        );
+
+        let mut rope = Rope::new(3, FollowRule::Diagonal);
+        rope.apply(&Instruction::from("R 2"));
+        rope.apply(&Instruction::from("U 1"));
+        assert_eq!(visualize(rope.knots(), None), "..0\n21.\n");
+        assert_eq!(visualize(rope.knots(), Bounds::parse("-1,0,1,0")), ".21\n");
+    }
+
+    #[test]
+    fn recording() {
+        let mut rope = Rope::new(2, FollowRule::Diagonal);
+        rope.apply(&Instruction::from("R 2"));
+        let recording = Recording::from_rope(&rope);
+
+        let mut out = vec![];
+        recording.replay(&mut out, 1000.0, 1, None).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.matches("Step ").count(), 3);
+        assert!(out.ends_with("Step 2/2\ns10\n"));
+
+        let dir = env::temp_dir().join(format!("day9-frames-{}", std::process::id()));
+        let written = recording.write_ppm_frames(&dir, 2, 2, None).unwrap();
+        let image = fs::read(dir.join("frame000002.ppm")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(written, 2);
+        let header = b"P6\n6 2\n255\n";
+        assert!(image.starts_with(header));
+        // The start in black, then the tail in blue and the head in red.
+        let row = [[0, 0, 0], [0, 0, 255], [255, 0, 0]].map(|cell| [cell, cell].concat());
+        assert_eq!(image[header.len()..], [row.concat(), row.concat()].concat());
+    }
+
+    #[test]
+    fn follow_rules() {
+        let run = |rule, moves: &[&str]| {
+            let mut rope = Rope::new(2, rule);
+            moves
+                .iter()
+                .for_each(|&m| rope.apply(&Instruction::from(m)));
+            rope
+        };
+
+        let rope = run(FollowRule::Orthogonal, &["R 1", "U 2"]);
+        assert_eq!((rope.knots()[1].x, rope.knots()[1].y), (0, 1));
+        let rope = run(FollowRule::Diagonal, &["R 1", "U 2"]);
+        assert_eq!((rope.knots()[1].x, rope.knots()[1].y), (1, 1));
+
+        let rope = run(FollowRule::Slack(2), &["R 4"]);
+        assert_eq!(rope.knots()[1].x, 2);
+        assert_eq!(rope.visited(1), 3);
+
+        // The tail jumps from 0 to 2 on the third step, going over 1.
+        let rope = run(FollowRule::Elastic(3), &["R 4"]);
+        assert_eq!(rope.knots()[1].x, 2);
+        assert_eq!(rope.visited(1), 3);
+        let rope = run(FollowRule::Elastic(4), &["R 3", "U 3"]);
+        assert_eq!(rope.visited(1), rope.visited_points(1).len() as u64);
+        assert_eq!(rope.visited(1), 3);
+
+        assert_eq!(FollowRule::parse("slack:2"), Some(FollowRule::Slack(2)));
+        assert_eq!(FollowRule::parse("elastic:0"), None);
+    }
+
+    #[test]
+    fn bulk_steps() {
+        let mut seed = 7u32;
+        let instructions = (0..300)
+            .map(|_| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                let dir = ["R", "U", "L", "D"][(seed >> 16) as usize % 4];
+                Instruction::from(format!("{} {}", dir, (seed >> 20) % 40 + 1).as_str())
+            })
+            .collect::<Vec<_>>();
+
+        // Elastic ropes moving on every step follow the standard rule but
+        // never take the bulk path.
+        let mut bulk = Rope::new(10, FollowRule::Diagonal);
+        let mut stepped = Rope::new(10, FollowRule::Elastic(1));
+        for instruction in &instructions {
+            bulk.apply(instruction);
+            stepped.apply(instruction);
+        }
+        for knot in 0..10 {
+            assert_eq!(bulk.history(knot), stepped.history(knot));
+            assert_eq!(bulk.visited(knot), bulk.visited_points(knot).len() as u64);
+            assert_eq!(bulk.visited(knot), stepped.visited(knot));
+        }
+
+        // A square around the origin, the tail cuts every corner.
+        let square = |side: u64| {
+            let mut rope = Rope::new(10, FollowRule::Diagonal);
+            for dir in ["R", "U", "L", "D"] {
+                rope.apply(&Instruction::from(format!("{} {}", dir, side).as_str()));
+            }
+            rope
+        };
+        let rope = square(100);
+        assert_eq!(rope.visited(9), rope.visited_points(9).len() as u64);
+        assert_eq!(rope.visited(9), 4 * 100 - 35);
+        let rope = square(4_000_000_000);
+        assert_eq!(rope.visited(9), 4 * 4_000_000_000 - 35);
+        let svg = path_svg(rope.trail(9));
+        assert!(svg.len() < 4096);
+        let bottom =
+            r##"<rect x="0" y="40000000000" width="39999999920" height="10" fill="#9bc"/>"##;
+        assert!(svg.contains(bottom));
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{move_in_dir, Direction, Point};
+
+/// How a knot moves after the knot in front of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FollowRule {
+    /// The puzzle's rule, step diagonally when not in line, else along the axis.
+    Diagonal,
+    /// Only ever step along an axis, closing the larger gap first.
+    Orthogonal,
+    /// Like `Diagonal`, but a knot may stay up to `n` cells away.
+    Slack(i64),
+    /// Knots only move on every `k`th step, but then catch up until touching.
+    Elastic(u64),
+}
+
+/// Rules compared side by side by `--compare`.
+pub const COMPARED_RULES: [FollowRule; 6] = [
+    FollowRule::Diagonal,
+    FollowRule::Orthogonal,
+    FollowRule::Slack(2),
+    FollowRule::Slack(3),
+    FollowRule::Elastic(2),
+    FollowRule::Elastic(5),
+];
+
+impl FollowRule {
+    /// Parses `diagonal`, `orthogonal`, `slack:N` or `elastic:K`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (name, arg) = match s.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (s, None),
+        };
+        match (name, arg) {
+            ("diagonal", None) => Some(Self::Diagonal),
+            ("orthogonal", None) => Some(Self::Orthogonal),
+            ("slack", Some(n)) => n.parse().ok().filter(|&n| n > 0).map(Self::Slack),
+            ("elastic", Some(k)) => k.parse().ok().filter(|&k| k > 0).map(Self::Elastic),
+            _ => None,
+        }
+    }
+
+    /// Whether a knot's move only depends on where it and its head are. Such
+    /// rules look the same after every step of a straight run once the whole
+    /// rope moves along, so the rest of the run can be taken in one go.
+    pub fn is_translation_invariant(self) -> bool {
+        !matches!(self, Self::Elastic(_))
+    }
+
+    /// Moves `child` after `head` has moved in the `step`th step, counting
+    /// from one. `on_move` sees every cell the child steps on, as it may take
+    /// several steps to catch up.
+    pub fn follow(self, head: &Point, child: &mut Point, step: u64, on_move: impl FnMut(&Point)) {
+        match self {
+            Self::Diagonal => catch_up(head, child, 1, diagonal_step, on_move),
+            Self::Orthogonal => catch_up(head, child, 1, orthogonal_step, on_move),
+            Self::Slack(n) => catch_up(head, child, n, diagonal_step, on_move),
+            Self::Elastic(k) if step.is_multiple_of(k) => {
+                catch_up(head, child, 1, diagonal_step, on_move)
+            }
+            Self::Elastic(_) => {}
+        }
+    }
+}
+
+impl std::fmt::Display for FollowRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Diagonal => write!(f, "diagonal"),
+            Self::Orthogonal => write!(f, "orthogonal"),
+            Self::Slack(n) => write!(f, "slack:{}", n),
+            Self::Elastic(k) => write!(f, "elastic:{}", k),
+        }
+    }
+}
+
+fn distance(head: &Point, child: &Point) -> i64 {
+    (head.x - child.x).abs().max((head.y - child.y).abs())
+}
+
+fn catch_up(
+    head: &Point,
+    child: &mut Point,
+    slack: i64,
+    step: fn(&Point, &mut Point),
+    mut on_move: impl FnMut(&Point),
+) {
+    while distance(head, child) > slack {
+        step(head, child);
+        on_move(child);
+    }
+}
+
+fn axis_dir(x_dir: i64, y_dir: i64) -> Direction {
+    match (x_dir, y_dir) {
+        (1, _) => Direction::Right,
+        (-1, _) => Direction::Left,
+        (_, 1) => Direction::Up,
+        (_, -1) => Direction::Down,
+        _ => unreachable!(),
+    }
+}
+
+fn diagonal_step(head: &Point, child: &mut Point) {
+    let x_dir = (head.x - child.x).signum();
+    let y_dir = (head.y - child.y).signum();
+
+    if child.x == head.x || child.y == head.y {
+        move_in_dir(&axis_dir(x_dir, y_dir), child)
+    } else {
+        child.x += x_dir;
+        child.y += y_dir;
+    }
+}
+
+fn orthogonal_step(head: &Point, child: &mut Point) {
+    let diff_x = head.x - child.x;
+    let diff_y = head.y - child.y;
+
+    let dir = if diff_x.abs() >= diff_y.abs() {
+        axis_dir(diff_x.signum(), 0)
+    } else {
+        axis_dir(0, diff_y.signum())
+    };
+    move_in_dir(&dir, child)
+}
@@ -0,0 +1,184 @@
+use std::{collections::HashSet, fmt::Write};
+
+use crate::{trail::Trail, Point};
+
+const KNOT_LABELS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Inclusive rectangle of the board, `y` grows upwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub min_x: i64,
+    pub min_y: i64,
+    pub max_x: i64,
+    pub max_y: i64,
+}
+
+impl Bounds {
+    /// Smallest rectangle containing the start and all the given points.
+    pub fn around<'a>(points: impl IntoIterator<Item = &'a Point>) -> Self {
+        points.into_iter().fold(
+            Self {
+                min_x: 0,
+                min_y: 0,
+                max_x: 0,
+                max_y: 0,
+            },
+            |b, p| Self {
+                min_x: b.min_x.min(p.x),
+                min_y: b.min_y.min(p.y),
+                max_x: b.max_x.max(p.x),
+                max_y: b.max_y.max(p.y),
+            },
+        )
+    }
+
+    /// Parses `min_x,min_y,max_x,max_y`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let parts = s
+            .split(',')
+            .map(|p| p.trim().parse().ok())
+            .collect::<Option<Vec<i64>>>()?;
+        match parts[..] {
+            [min_x, min_y, max_x, max_y] if min_x <= max_x && min_y <= max_y => Some(Self {
+                min_x,
+                min_y,
+                max_x,
+                max_y,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max_x - self.min_x) as usize + 1
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max_y - self.min_y) as usize + 1
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        (self.min_x..=self.max_x).contains(&p.x) && (self.min_y..=self.max_y).contains(&p.y)
+    }
+}
+
+/// Character board covering `bounds`, drawn top row first.
+struct Board {
+    bounds: Bounds,
+    cells: Vec<Vec<u8>>,
+}
+
+impl Board {
+    fn new(bounds: Bounds) -> Self {
+        Self {
+            bounds,
+            cells: vec![vec![b'.'; bounds.width()]; bounds.height()],
+        }
+    }
+
+    fn mark_start(&mut self) {
+        self.set(&Point::new(0, 0), b's');
+    }
+
+    fn set(&mut self, p: &Point, c: u8) {
+        if self.bounds.contains(p) {
+            let row = (self.bounds.max_y - p.y) as usize;
+            let column = (p.x - self.bounds.min_x) as usize;
+            self.cells[row][column] = c;
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::with_capacity(self.cells.len() * (self.bounds.width() + 1));
+        for row in &self.cells {
+            out.push_str(std::str::from_utf8(row).unwrap());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Draws the knots of a rope, labelled by index, with the head on top. Knots
+/// past the 62nd are drawn as `*`. Without a viewport the board grows to fit.
+pub fn visualize(knots: &[Point], viewport: Option<Bounds>) -> String {
+    let mut board = Board::new(viewport.unwrap_or_else(|| Bounds::around(knots)));
+    board.mark_start();
+    for (i, knot) in knots.iter().enumerate().rev() {
+        board.set(knot, KNOT_LABELS.get(i).copied().unwrap_or(b'*'));
+    }
+    board.render()
+}
+
+pub fn visualize_path(path: &HashSet<Point>, viewport: Option<Bounds>) -> String {
+    let mut board = Board::new(viewport.unwrap_or_else(|| Bounds::around(path)));
+    for point in path {
+        board.set(point, b'#');
+    }
+    board.mark_start();
+    board.render()
+}
+
+/// SVG of every cell a knot visited, with the route it took on top. Straight
+/// runs are drawn as single shapes, so long moves stay small.
+pub fn path_svg(trail: &Trail) -> String {
+    const CELL: i64 = 10;
+    let corners = trail.corners().collect::<Vec<_>>();
+    let bounds = Bounds::around(&corners);
+    let x = |x: i64| (x - bounds.min_x) * CELL;
+    let y = |y: i64| (bounds.max_y - y) * CELL;
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = bounds.width() as i64 * CELL,
+        h = bounds.height() as i64 * CELL
+    )
+    .unwrap();
+    writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+
+    let (rows, columns) = trail.intervals();
+    let rects = rows
+        .iter()
+        .map(|&(row, start, end)| (x(start), y(row), end - start + 1, 1))
+        .chain(
+            columns
+                .iter()
+                .map(|&(column, start, end)| (x(column), y(end), 1, end - start + 1)),
+        );
+    for (left, top, width, height) in rects {
+        writeln!(
+            out,
+            r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#9bc"/>"##,
+            left,
+            top,
+            width * CELL,
+            height * CELL
+        )
+        .unwrap();
+    }
+
+    let points = corners
+        .iter()
+        .map(|p| format!("{},{}", x(p.x) + CELL / 2, y(p.y) + CELL / 2))
+        .collect::<Vec<_>>()
+        .join(" ");
+    writeln!(
+        out,
+        r##"<polyline points="{}" fill="none" stroke="#236" stroke-width="1"/>"##,
+        points
+    )
+    .unwrap();
+
+    let start = Point::new(0, 0);
+    writeln!(
+        out,
+        r#"<circle cx="{}" cy="{}" r="{}" fill="red"/>"#,
+        x(start.x) + CELL / 2,
+        y(start.y) + CELL / 2,
+        CELL / 3
+    )
+    .unwrap();
+    out.push_str("</svg>\n");
+    out
+}
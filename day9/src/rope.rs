@@ -0,0 +1,106 @@
+use std::collections::HashSet;
+
+use lending_iterator::{lending_iterator::constructors::windows_mut, LendingIterator};
+
+use crate::{move_in_dir, physics::FollowRule, trail::Trail, Direction, Instruction, Point};
+
+/// A rope of any number of knots, the first one being the head. Where every
+/// knot went is kept as a trail, starting from the origin.
+pub struct Rope {
+    knots: Vec<Point>,
+    trails: Vec<Trail>,
+    rule: FollowRule,
+    steps: u64,
+}
+
+impl Rope {
+    pub fn new(knots: usize, rule: FollowRule) -> Self {
+        assert!(knots > 0, "A rope needs at least a head");
+        Self {
+            knots: vec![Point::new(0, 0); knots],
+            trails: vec![Trail::default(); knots],
+            rule,
+            steps: 0,
+        }
+    }
+
+    pub fn knot_count(&self) -> usize {
+        self.knots.len()
+    }
+
+    pub fn knots(&self) -> &[Point] {
+        &self.knots
+    }
+
+    /// Moves the head one cell and the other knots after it. Returns whether
+    /// every knot moved by the same offset as the head.
+    fn step(&mut self, dir: &Direction) -> bool {
+        self.steps += 1;
+        let before = self.knots.clone();
+        move_in_dir(dir, self.knots.first_mut().unwrap());
+
+        // Cells knots went over on their way, but didn't stop on.
+        let mut passed = vec![];
+        let (rule, steps) = (self.rule, self.steps);
+        let mut knot = 0;
+        self.knots.windows_mut::<2>().for_each(|window| {
+            knot += 1;
+            let (head, child) = window.split_first_mut().unwrap();
+            let mut cells = vec![];
+            rule.follow(head, &mut child[0], steps, |p| cells.push(p.clone()));
+            cells.pop();
+            passed.extend(cells.into_iter().map(|p| (knot, p)));
+        });
+        for (knot, p) in passed {
+            self.trails[knot].pass(p);
+        }
+
+        let moves = before
+            .iter()
+            .zip(&self.knots)
+            .map(|(from, to)| (to.x - from.x, to.y - from.y))
+            .collect::<Vec<_>>();
+        for (trail, &(dx, dy)) in self.trails.iter_mut().zip(&moves) {
+            trail.push(dx, dy, 1);
+        }
+        moves.iter().all(|&m| m == moves[0])
+    }
+
+    /// Steps through an instruction until the whole rope moves along with
+    /// the head, which it then keeps doing for the rest of the run, so the
+    /// remaining steps are taken at once.
+    pub fn apply(&mut self, Instruction { dir, step }: &Instruction) {
+        let mut remaining = *step as u64;
+        while remaining > 0 {
+            remaining -= 1;
+            if self.step(dir) && self.rule.is_translation_invariant() {
+                let mut delta = Point::new(0, 0);
+                move_in_dir(dir, &mut delta);
+                for (knot, trail) in self.knots.iter_mut().zip(&mut self.trails) {
+                    knot.x += delta.x * remaining as i64;
+                    knot.y += delta.y * remaining as i64;
+                    trail.push(delta.x, delta.y, remaining);
+                }
+                self.steps += remaining;
+                break;
+            }
+        }
+    }
+
+    pub fn trail(&self, knot: usize) -> &Trail {
+        &self.trails[knot]
+    }
+
+    /// Positions of a knot after every step, the first one being the origin.
+    pub fn history(&self, knot: usize) -> Vec<Point> {
+        self.trails[knot].positions().collect()
+    }
+
+    pub fn visited_points(&self, knot: usize) -> HashSet<Point> {
+        self.trails[knot].cells().collect()
+    }
+
+    pub fn visited(&self, knot: usize) -> u64 {
+        self.trails[knot].visited()
+    }
+}
@@ -0,0 +1,178 @@
+use crate::Point;
+
+/// Cells `start..=end` of a row or column, as `(line, start, end)`.
+pub type Interval = (i64, i64, i64);
+
+/// Consecutive steps a knot took by the same offset, standing still included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Run {
+    dx: i64,
+    dy: i64,
+    len: u64,
+}
+
+/// Where a knot went, starting from the origin, stored as runs of equal
+/// steps so long straight moves take constant space. Cells a knot went over
+/// while moving more than one cell in a step are kept apart.
+#[derive(Debug, Clone, Default)]
+pub struct Trail {
+    runs: Vec<Run>,
+    passed: Vec<Point>,
+}
+
+impl Trail {
+    /// Records `len` steps of a knot moving by `(dx, dy)` each.
+    pub fn push(&mut self, dx: i64, dy: i64, len: u64) {
+        match self.runs.last_mut() {
+            Some(run) if run.dx == dx && run.dy == dy => run.len += len,
+            _ if len == 0 => {}
+            _ => self.runs.push(Run { dx, dy, len }),
+        }
+    }
+
+    /// Records a cell the knot went over within a step without stopping.
+    pub fn pass(&mut self, p: Point) {
+        self.passed.push(p);
+    }
+
+    /// Positions after every step, the first one being the origin.
+    pub fn positions(&self) -> impl Iterator<Item = Point> + '_ {
+        let steps = self
+            .runs
+            .iter()
+            .flat_map(|run| std::iter::repeat_n((run.dx, run.dy), run.len as usize));
+        std::iter::once(Point::new(0, 0)).chain(steps.scan(Point::new(0, 0), |p, (dx, dy)| {
+            p.x += dx;
+            p.y += dy;
+            Some(p.clone())
+        }))
+    }
+
+    /// Every cell the knot has been on, with repeats.
+    pub fn cells(&self) -> impl Iterator<Item = Point> + '_ {
+        self.positions().chain(self.passed.iter().cloned())
+    }
+
+    /// Where the knot was at the start and the end of every run.
+    pub fn corners(&self) -> impl Iterator<Item = Point> + '_ {
+        std::iter::once(Point::new(0, 0)).chain(self.runs.iter().scan(
+            Point::new(0, 0),
+            |p, run| {
+                p.x += run.dx * run.len as i64;
+                p.y += run.dy * run.len as i64;
+                Some(p.clone())
+            },
+        ))
+    }
+
+    /// The cells the knot has been on as `(row, start, end)` and
+    /// `(column, start, end)` intervals, each merged with the ones it touches.
+    /// Straight runs of single cell steps become one interval each, other
+    /// moves and passed over cells a one cell row interval each.
+    pub fn intervals(&self) -> (Vec<Interval>, Vec<Interval>) {
+        let mut rows = vec![(0, 0, 0)];
+        rows.extend(self.passed.iter().map(|p| (p.y, p.x, p.x)));
+        let mut columns = vec![];
+        let mut p = Point::new(0, 0);
+        for &Run { dx, dy, len } in &self.runs {
+            match (dx, dy) {
+                (0, 0) => continue,
+                (-1 | 1, 0) => {
+                    let end = p.x + dx * len as i64;
+                    rows.push((p.y, p.x.min(end), p.x.max(end)));
+                    p.x = end;
+                }
+                (0, -1 | 1) => {
+                    let end = p.y + dy * len as i64;
+                    columns.push((p.x, p.y.min(end), p.y.max(end)));
+                    p.y = end;
+                }
+                _ => {
+                    for _ in 0..len {
+                        p.x += dx;
+                        p.y += dy;
+                        rows.push((p.y, p.x, p.x));
+                    }
+                }
+            }
+        }
+        (merge(rows), merge(columns))
+    }
+
+    /// Number of distinct cells the knot has been on, counting where row and
+    /// column intervals overlap without ever listing the cells.
+    pub fn visited(&self) -> u64 {
+        let (rows, columns) = self.intervals();
+        let length = |lines: &[Interval]| {
+            lines
+                .iter()
+                .map(|&(_, a, b)| (b - a + 1) as u64)
+                .sum::<u64>()
+        };
+        length(&rows) + length(&columns) - crossings(&rows, &columns)
+    }
+}
+
+/// Sorts `(line, start, end)` intervals and joins the ones that touch, so
+/// that every cell is covered at most once.
+fn merge(mut intervals: Vec<Interval>) -> Vec<Interval> {
+    intervals.sort_unstable();
+    let mut merged: Vec<Interval> = Vec::with_capacity(intervals.len());
+    for (line, start, end) in intervals {
+        match merged.last_mut() {
+            Some(last) if last.0 == line && start <= last.2 + 1 => last.2 = last.2.max(end),
+            _ => merged.push((line, start, end)),
+        }
+    }
+    merged
+}
+
+/// Counts cells covered by both a row and a column interval, sweeping
+/// left to right with the rows crossing the current column in a Fenwick tree.
+fn crossings(rows: &[Interval], columns: &[Interval]) -> u64 {
+    let mut ys = rows.iter().map(|&(y, _, _)| y).collect::<Vec<_>>();
+    ys.dedup();
+    let index = |y: i64| ys.partition_point(|&v| v < y);
+
+    // At equal x, rows that ended just before are removed first, then new
+    // rows added and only then columns counted.
+    let mut events = Vec::with_capacity(rows.len() * 2 + columns.len());
+    for &(y, x1, x2) in rows {
+        events.push((x1, 0, y, y));
+        events.push((x2 + 1, -1, y, y));
+    }
+    for &(x, y1, y2) in columns {
+        events.push((x, 1, y1, y2));
+    }
+    events.sort_unstable();
+
+    let mut tree = vec![0i64; ys.len() + 1];
+    let add = |tree: &mut Vec<i64>, i: usize, v: i64| {
+        let mut i = i + 1;
+        while i < tree.len() {
+            tree[i] += v;
+            i += i & i.wrapping_neg();
+        }
+    };
+    let prefix = |tree: &Vec<i64>, mut i: usize| {
+        let mut sum = 0;
+        while i > 0 {
+            sum += tree[i];
+            i &= i - 1;
+        }
+        sum
+    };
+
+    let mut count = 0;
+    for (_, kind, a, b) in events {
+        match kind {
+            0 => add(&mut tree, index(a), 1),
+            -1 => add(&mut tree, index(a), -1),
+            _ => {
+                let hits = prefix(&tree, index(b + 1)) - prefix(&tree, index(a));
+                count += hits as u64;
+            }
+        }
+    }
+    count
+}